@@ -1,22 +1,171 @@
+//! Contains the reward schedule of the DAO, the USD amounts paid for each kind of payout
+
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+
 use crate::types::USD;
+use crate::*;
+
+/// A prize of a hackathon or meme contest
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Prize {
+    /// the category of the prize, none for the overall ranking
+    pub category: Option<String>,
+    /// the rank of the winner in the category, starting at 1
+    pub rank: u64,
+    /// the amount paid to the winner
+    pub amount: USD,
+}
+
+/// Check the ranks of each category of a prize table start at 1 and follow
+/// each other, and that the amounts are positive and do not increase with
+/// the rank. Returns the total amount of the prizes.
+pub fn assert_valid_prize_table(prizes: &[Prize]) -> USD {
+    let mut last_prizes: HashMap<&Option<String>, &Prize> = HashMap::new();
+    let mut total = USD(0);
+    for prize in prizes {
+        let is_valid = match last_prizes.get(&prize.category) {
+            Some(last) => prize.rank == last.rank + 1 && prize.amount <= last.amount,
+            None => prize.rank == 1,
+        };
+        if !is_valid || prize.amount.0 == 0 {
+            panic!("{}", error::ERR_INVALID_PRIZE_TABLE);
+        }
+        last_prizes.insert(&prize.category, prize);
+        total = total
+            .checked_add(prize.amount)
+            .expect(error::ERR_AMOUNT_OVERFLOW);
+    }
+    total
+}
+
+/// build a prize table of the overall ranking from the amounts of each rank
+fn ranked_prizes(amounts: &[u64]) -> Vec<Prize> {
+    amounts
+        .iter()
+        .enumerate()
+        .map(|(index, amount)| Prize {
+            category: None,
+            rank: index as u64 + 1,
+            amount: USD::from_dollars(*amount),
+        })
+        .collect()
+}
+
+/// The amounts paid for approved payouts. Every change of the schedule is
+/// stored as a new version, and a payout is paid with the version that was
+/// current when it was submitted.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RewardSchedule {
+    pub hackathon_completion: USD,
+    pub meme_contest_completion: USD,
+    pub webinar_completion: USD,
+    pub content_coordination: USD,
+
+    pub ca_register_referral: USD,
+    pub recruitment_referral: USD,
+    pub campus_mou: USD,
+    pub ncd_completion_referral: USD,
+    pub ncd_form_filled_referral: USD,
+
+    /// the prizes of a hackathon without its own prize table, in the order
+    /// of the winners
+    pub hackathon_prizes: Vec<Prize>,
+    /// the prizes of a meme contest without its own prize table, in the order
+    /// of the winners
+    pub meme_contest_prizes: Vec<Prize>,
+
+    pub ca_bonus: USD,
+}
+
+impl RewardSchedule {
+    /// check the prize tables are well formed
+    pub fn assert_valid(&self) {
+        assert_valid_prize_table(&self.hackathon_prizes);
+        assert_valid_prize_table(&self.meme_contest_prizes);
+    }
+}
+
+impl Default for RewardSchedule {
+    /// The amounts paid before the schedule was stored on chain.
+    fn default() -> Self {
+        Self {
+            hackathon_completion: USD::from_dollars(40),
+            meme_contest_completion: USD::from_dollars(20),
+            webinar_completion: USD::from_dollars(20),
+            content_coordination: USD::from_dollars(25),
+
+            ca_register_referral: USD::from_dollars(5),
+            recruitment_referral: USD::from_dollars(10),
+            campus_mou: USD::from_dollars(100),
+            ncd_completion_referral: USD::from_dollars(5),
+            ncd_form_filled_referral: USD::from_dollars(1),
+
+            hackathon_prizes: ranked_prizes(&[150, 10, 5]),
+            meme_contest_prizes: ranked_prizes(&[75, 50, 35]),
+
+            ca_bonus: USD::from_dollars(125),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the given version of the reward schedule, the current one by default.
+    pub fn get_reward_schedule(&self, version: Option<u64>) -> RewardSchedule {
+        self.internal_reward_schedule(version.unwrap_or(self.reward_schedule_version))
+    }
+
+    /// Returns the version of the current reward schedule.
+    pub fn get_reward_schedule_version(&self) -> u64 {
+        self.reward_schedule_version
+    }
+}
 
-pub const HACKATHON_COMPLETION_AMOUNT: USD = 40.;
-pub const MEME_CONTEST_COMPLETION_AMOUNT: USD = 20.;
-pub const WEBINAR_COMPLETION_AMOUNT: USD = 20.;
-pub const CONTENT_COORDINATION_AMOUNT: USD = 25.;
+impl Contract {
+    pub(crate) fn internal_reward_schedule(&self, version: u64) -> RewardSchedule {
+        self.reward_schedules
+            .get(&version)
+            .expect(error::ERR_REWARD_SCHEDULE_NOT_FOUND)
+    }
 
-pub const CA_REGISTER_REFERRAL_AMOUNT: USD = 5.;
-pub const RECRUITMENT_REFERRAL_AMOUNT: USD = 10.;
-pub const CAMPUS_MOU_AMOUNT: USD = 100.;
-pub const NCD_COMPLETION_REFERRAL_AMOUNT: USD = 5.;
-pub const NCD_FORM_FILLED_REFERRAL_AMOUNT: USD = 1.;
+    /// store a new version of the reward schedule and make it the current one
+    pub(crate) fn internal_set_reward_schedule(&mut self, schedule: &RewardSchedule) {
+        self.reward_schedule_version += 1;
+        self.reward_schedules
+            .insert(&self.reward_schedule_version, schedule);
+    }
+}
 
-pub const HACKATHON_FIRST_PLACE_AMOUNT: USD = 150.;
-pub const HACKATHON_SECOND_PLACE_AMOUNT: USD = 10.;
-pub const HACKATHON_THIRD_PLACE_AMOUNT: USD = 5.;
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-pub const MEME_CONTEST_FIRST_PLACE_AMOUNT: USD = 75.;
-pub const MEME_CONTEST_SECOND_PLACE_AMOUNT: USD = 50.;
-pub const MEME_CONTEST_THIRD_PLACE_AMOUNT: USD = 35.;
+    #[test]
+    fn validates_prize_tables() {
+        let mut prizes = ranked_prizes(&[100, 50, 50]);
+        prizes.push(Prize {
+            category: Some("design".to_string()),
+            rank: 1,
+            amount: USD::from_dollars(200),
+        });
+        assert_eq!(assert_valid_prize_table(&prizes), USD::from_dollars(400));
+        assert_valid_prize_table(&RewardSchedule::default().hackathon_prizes);
+    }
 
-pub const CA_BONUS_AMOUNT: USD = 125.;
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PRIZE_TABLE")]
+    fn rejects_skipped_ranks() {
+        let mut prizes = ranked_prizes(&[100]);
+        prizes.push(Prize {
+            category: None,
+            rank: 3,
+            amount: USD::from_dollars(50),
+        });
+        assert_valid_prize_table(&prizes);
+    }
+}
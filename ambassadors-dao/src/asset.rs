@@ -0,0 +1,104 @@
+//! Contains the assets the payouts are paid in
+
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::Balance;
+
+use crate::payout::PayoutKind;
+use crate::*;
+
+/// Maximum number of decimals of a stablecoin
+pub const MAX_STABLECOIN_DECIMALS: u8 = 24;
+
+/// An asset in which payments are made
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PayoutAsset {
+    /// native NEAR tokens, converted with the exchange rate of the oracle
+    Near,
+    /// a NEP-141 token pegged to the dollar, transferred without an exchange rate
+    Stablecoin {
+        /// the account of the token contract
+        token_id: AccountId,
+        /// the number of decimals of the token
+        decimals: u8,
+    },
+}
+
+impl PayoutAsset {
+    /// Converts an amount of dollars to the smallest unit of a stablecoin,
+    /// the result is rounded down.
+    pub fn usd_to_token_amount(amount: USD, decimals: u8) -> Balance {
+        let amount = amount.0 as u128;
+        let micro_usd = types::MICRO_USD_PER_USD as u128;
+        if decimals >= 6 {
+            amount * 10_u128.pow(decimals as u32 - 6)
+        } else {
+            amount * 10_u128.pow(decimals as u32) / micro_usd
+        }
+    }
+}
+
+/// The assets payouts are paid in, with optional overrides per kind of payout.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PayoutAssets {
+    /// the asset of every payout kind without an override
+    pub default_asset: PayoutAsset,
+    /// assets for specific payout kinds
+    pub overrides: HashMap<PayoutKind, PayoutAsset>,
+}
+
+impl PayoutAssets {
+    /// check every asset is well formed
+    pub fn assert_valid(&self) {
+        for asset in std::iter::once(&self.default_asset).chain(self.overrides.values()) {
+            if let PayoutAsset::Stablecoin { decimals, .. } = asset {
+                if *decimals > MAX_STABLECOIN_DECIMALS {
+                    panic!("{}", error::ERR_INVALID_PAYOUT_ASSETS);
+                }
+            }
+        }
+    }
+
+    /// get the asset a payout kind is paid in
+    pub fn asset_for(&self, kind: &PayoutKind) -> &PayoutAsset {
+        self.overrides.get(kind).unwrap_or(&self.default_asset)
+    }
+}
+
+impl Default for PayoutAssets {
+    /// Every payout is paid in NEAR.
+    fn default() -> Self {
+        Self {
+            default_asset: PayoutAsset::Near,
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the assets payouts are paid in.
+    pub fn get_payout_assets(&self) -> PayoutAssets {
+        self.payout_assets.clone()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_usd_to_token_amount() {
+        let amount = USD(12_345_678);
+        assert_eq!(PayoutAsset::usd_to_token_amount(amount, 6), 12_345_678);
+        assert_eq!(
+            PayoutAsset::usd_to_token_amount(amount, 18),
+            12_345_678_000_000_000_000
+        );
+        assert_eq!(PayoutAsset::usd_to_token_amount(amount, 2), 1_234);
+    }
+}
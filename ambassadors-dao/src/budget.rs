@@ -0,0 +1,253 @@
+//! Contains the budget caps of the DAO and the tracking of the amounts
+//! approved for each kind of payout
+
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::payout::PayoutKind;
+use crate::*;
+
+/// What happens when approving a payout would exceed its budget
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum BudgetMode {
+    /// the payout stays under consideration until the budget allows it
+    Block,
+    /// the payout is approved and paid, and flagged as over budget
+    Flag,
+}
+
+/// The outcome of recording the amount of an approved payout against its budget
+#[derive(Clone, Debug, PartialEq)]
+pub enum BudgetStatus {
+    /// the amount is within the budget
+    Within,
+    /// the budget is exceeded and the payout is flagged
+    Exceeded,
+    /// the budget would be exceeded, nothing was recorded and the payout
+    /// must stay under consideration
+    Blocked,
+}
+
+/// The maximum amount that can be approved for a kind of payout per period
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BudgetCap {
+    /// the maximum amount approved in a period
+    pub limit: USD,
+    /// the length of a period in nanoseconds, periods start at multiples
+    /// of the length since the unix epoch
+    pub period: U64,
+    /// what happens when the limit would be exceeded
+    pub mode: BudgetMode,
+}
+
+/// The amount approved for a kind of payout in its current period
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BudgetUsage {
+    /// the timestamp at which the period started
+    pub period_start: U64,
+    /// the amount approved since the start of the period
+    pub spent: USD,
+}
+
+/// The budget of a kind of payout in the current period
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct BudgetUtilization {
+    pub kind: PayoutKind,
+    pub cap: BudgetCap,
+    /// the timestamp at which the current period started
+    pub period_start: U64,
+    /// the timestamp at which the current period ends
+    pub period_end: U64,
+    /// the amount approved in the current period
+    pub spent: USD,
+    /// the amount that can still be approved in the current period, zero
+    /// if the budget was exceeded
+    pub remaining: USD,
+}
+
+/// Budget caps per kind of payout, kinds without a cap are unlimited.
+#[derive(
+    BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq, Default,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Budgets {
+    pub caps: HashMap<PayoutKind, BudgetCap>,
+}
+
+impl Budgets {
+    /// check every cap is well formed, governance proposals pay nothing so
+    /// they cannot have a cap
+    pub fn assert_valid(&self) {
+        for (kind, cap) in self.caps.iter() {
+            if *kind == PayoutKind::Governance || cap.period.0 == 0 {
+                panic!("{}", error::ERR_INVALID_BUDGETS);
+            }
+        }
+    }
+}
+
+impl BudgetCap {
+    /// the timestamp at which the period containing the given timestamp started
+    fn period_start(&self, timestamp: u64) -> u64 {
+        timestamp - timestamp % self.period.0
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the budget caps of this contract.
+    pub fn get_budgets(&self) -> Budgets {
+        self.budgets.clone()
+    }
+
+    /// Returns the utilization of the budget of every capped kind of payout
+    /// in the current period.
+    pub fn get_budget_utilization(&self) -> Vec<BudgetUtilization> {
+        self.budgets
+            .caps
+            .iter()
+            .map(|(kind, cap)| {
+                let period_start = cap.period_start(env::block_timestamp());
+                let spent = self.internal_budget_spent(kind, cap);
+                BudgetUtilization {
+                    kind: kind.clone(),
+                    cap: cap.clone(),
+                    period_start: U64(period_start),
+                    period_end: U64(period_start.saturating_add(cap.period.0)),
+                    spent,
+                    remaining: USD(cap.limit.0.saturating_sub(spent.0)),
+                }
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    /// the amount approved for a kind of payout in the current period of its cap
+    fn internal_budget_spent(&self, kind: &PayoutKind, cap: &BudgetCap) -> USD {
+        let period_start = cap.period_start(env::block_timestamp());
+        match self.budget_usage.get(kind) {
+            Some(usage) if usage.period_start.0 == period_start => usage.spent,
+            _ => USD(0),
+        }
+    }
+
+    /// Record the amount of an approved payout against the budget of its kind,
+    /// unless the budget would be exceeded and the cap blocks.
+    pub(crate) fn internal_spend_budget(&mut self, kind: &PayoutKind, amount: USD) -> BudgetStatus {
+        let cap = match self.budgets.caps.get(kind) {
+            Some(cap) => cap.clone(),
+            None => return BudgetStatus::Within,
+        };
+        let spent = self
+            .internal_budget_spent(kind, &cap)
+            .checked_add(amount)
+            .expect(error::ERR_AMOUNT_OVERFLOW);
+        let status = if spent <= cap.limit {
+            BudgetStatus::Within
+        } else {
+            match cap.mode {
+                BudgetMode::Block => {
+                    env::log_str(&format!(
+                        "{}: {:?} budget of {} would be exceeded",
+                        error::ERR_BUDGET_EXCEEDED,
+                        kind,
+                        cap.limit
+                    ));
+                    return BudgetStatus::Blocked;
+                }
+                BudgetMode::Flag => {
+                    env::log_str(&format!("{:?} budget of {} exceeded", kind, cap.limit));
+                    BudgetStatus::Exceeded
+                }
+            }
+        };
+        self.budget_usage.insert(
+            kind.clone(),
+            BudgetUsage {
+                period_start: U64(cap.period_start(env::block_timestamp())),
+                spent,
+            },
+        );
+        status
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::payout::fixtures::{contract_with_proposal, open_proposal};
+    use crate::payout::PayoutStatus;
+    use crate::vote::{Vote, VoteRecord};
+
+    /// a contract with a $10 proposal approved by its council, and a cap of $5
+    /// on proposals in the given mode
+    fn contract_over_budget(mode: BudgetMode) -> Contract {
+        testing_env!(VMContextBuilder::new().build());
+        let mut payout = open_proposal();
+        payout.record_vote(
+            accounts(0),
+            VoteRecord {
+                vote: Vote::Approve,
+                delegate: None,
+                comment: None,
+            },
+        );
+        let mut contract = contract_with_proposal(payout);
+        contract.budgets.caps.insert(
+            PayoutKind::Proposal,
+            BudgetCap {
+                limit: USD::from_dollars(5),
+                period: U64(30),
+                mode,
+            },
+        );
+        contract
+    }
+
+    #[test]
+    fn periods_start_at_multiples_of_their_length() {
+        let cap = BudgetCap {
+            limit: USD::from_dollars(100),
+            period: U64(30),
+            mode: BudgetMode::Block,
+        };
+        assert_eq!(cap.period_start(0), 0);
+        assert_eq!(cap.period_start(29), 0);
+        assert_eq!(cap.period_start(30), 30);
+        assert_eq!(cap.period_start(75), 60);
+    }
+
+    #[test]
+    fn blocked_payout_stays_under_consideration() {
+        let mut contract = contract_over_budget(BudgetMode::Block);
+        contract.finalize_payout(PayoutKind::Proposal, 1);
+        let payout = contract.proposals.get(&1).unwrap();
+        assert_eq!(payout.status, PayoutStatus::UnderConsideration);
+        assert!(payout.payments.is_empty());
+        assert_eq!(contract.get_budget_utilization()[0].spent, USD(0));
+    }
+
+    #[test]
+    fn flagged_payout_is_approved_over_budget() {
+        let mut contract = contract_over_budget(BudgetMode::Flag);
+        contract.finalize_payout(PayoutKind::Proposal, 1);
+        let payout = contract.proposals.get(&1).unwrap();
+        assert_eq!(payout.status, PayoutStatus::Approved);
+        assert!(payout.over_budget);
+        assert_eq!(
+            contract.get_budget_utilization()[0].spent,
+            USD::from_dollars(10)
+        );
+    }
+}
@@ -0,0 +1,111 @@
+//! Contains the delegation of votes between council members
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::payout::PayoutKind;
+use crate::types::Action;
+use crate::*;
+
+/// A delegation of the vote of a council member to another one for a time window
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Delegation {
+    /// the council member voting on behalf of the delegator
+    pub delegate: AccountId,
+    /// the timestamp at which the delegation starts
+    pub start: U64,
+    /// the timestamp at which the delegation ends
+    pub end: U64,
+}
+
+impl Delegation {
+    /// if the delegation is in effect at the given timestamp
+    pub fn is_active(&self, timestamp: u64) -> bool {
+        self.start.0 <= timestamp && timestamp < self.end.0
+    }
+}
+
+/// A delegation in effect, as returned by `get_active_delegations`
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct ActiveDelegation {
+    pub delegator: AccountId,
+    #[serde(flatten)]
+    pub delegation: Delegation,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Delegate the vote of the signer to another council member from `start`,
+    /// now by default, until `end`. Replaces the previous delegation of the signer.
+    /// Can only be done by a council member
+    pub fn delegate_vote(&mut self, delegate: AccountId, start: Option<U64>, end: U64) {
+        let delegator = env::signer_account_id();
+        if !self.members.is_council_member(&delegator) {
+            panic!("{}", error::ERR_NOT_PERMITTED);
+        }
+        if delegate == delegator || !self.members.is_council_member(&delegate) {
+            panic!("{}", error::ERR_INVALID_DELEGATION);
+        }
+        let start = start.unwrap_or(U64(env::block_timestamp()));
+        if end.0 <= start.0 || end.0 <= env::block_timestamp() {
+            panic!("{}", error::ERR_INVALID_DELEGATION);
+        }
+        self.delegations.insert(
+            delegator,
+            Delegation {
+                delegate,
+                start,
+                end,
+            },
+        );
+    }
+
+    /// Remove the delegation of the signer, the votes already cast by the
+    /// delegate are kept.
+    pub fn revoke_delegation(&mut self) {
+        if self.delegations.remove(&env::signer_account_id()).is_none() {
+            panic!("{}", error::ERR_DELEGATION_NOT_FOUND);
+        }
+    }
+
+    /// Returns the delegations in effect.
+    pub fn get_active_delegations(&self) -> Vec<ActiveDelegation> {
+        let now = env::block_timestamp();
+        self.delegations
+            .iter()
+            .filter(|(_, delegation)| delegation.is_active(now))
+            .map(|(delegator, delegation)| ActiveDelegation {
+                delegator: delegator.clone(),
+                delegation: delegation.clone(),
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    /// The council members whose vote is delegated to the given account and
+    /// who are allowed to take the action on payouts of the given kind.
+    pub(crate) fn internal_active_delegators(
+        &self,
+        delegate: &AccountId,
+        kind: &PayoutKind,
+        action: &Action,
+    ) -> Vec<AccountId> {
+        let now = env::block_timestamp();
+        self.delegations
+            .iter()
+            .filter(|(delegator, delegation)| {
+                &delegation.delegate == delegate
+                    && delegation.is_active(now)
+                    && self.members.is_council_member(delegator)
+                    && self
+                        .policy
+                        .is_allowed(&self.members, delegator, kind, action)
+            })
+            .map(|(delegator, _)| delegator.clone())
+            .collect()
+    }
+}
@@ -3,10 +3,55 @@ pub const ERR_PROPOSAL_NOT_FOUND: &str = "ERR_PROPOSAL_NOT_FOUND";
 pub const ERR_BOUNTY_NOT_FOUND: &str = "ERR_BOUNTY_NOT_FOUND";
 pub const ERR_MISCELLANEOUS_NOT_FOUND: &str = "ERR_MISCELLANEOUS_NOT_FOUND";
 pub const ERR_REFERRAL_NOT_FOUND: &str = "ERR_REFERRAL_NOT_FOUND";
+pub const ERR_GOVERNANCE_PROPOSAL_NOT_FOUND: &str = "ERR_GOVERNANCE_PROPOSAL_NOT_FOUND";
 pub const ERR_GENERATING_RANDOM_NUMBER: &str = "ERR_GENERATING_RANDOM_NUMBER";
 pub const ERR_CONTRACT_NOT_INITIALIZED: &str = "ERR_CONTRACT_NOT_INITIALIZED";
+pub const ERR_NOTHING_TO_MIGRATE: &str = "ERR_NOTHING_TO_MIGRATE";
 pub const ERR_AMBASSADOR_NOT_FOUND: &str = "ERR_AMBASSADOR_NOT_FOUND";
 pub const ERR_REFERRED_MEMBER_NOT_FOUND: &str = "ERR_REFERRED_MEMBER_NOT_FOUND";
 pub const ERR_INVALID_RESOURCE_URL: &str = "ERR_INVALID_RESOURCE_URL";
 pub const ERR_INVALID_ACTION: &str = "ERR_INVALID_ACTION";
 pub const ERR_INVALID_REFERRAL_TOKEN: &str = "ERR_INVALID_REFERRAL_TOKEN";
+pub const ERR_INVALID_VOTE_POLICY: &str = "ERR_INVALID_VOTE_POLICY";
+pub const ERR_INVALID_EXCHANGE_RATE: &str = "ERR_INVALID_EXCHANGE_RATE";
+pub const ERR_AMOUNT_OVERFLOW: &str = "ERR_AMOUNT_OVERFLOW";
+pub const ERR_INVALID_RATE_LIMITS: &str = "ERR_INVALID_RATE_LIMITS";
+pub const ERR_INVALID_ORACLE_CONFIG: &str = "ERR_INVALID_ORACLE_CONFIG";
+pub const ERR_INVALID_PAYOUT_ASSETS: &str = "ERR_INVALID_PAYOUT_ASSETS";
+pub const ERR_REWARD_SCHEDULE_NOT_FOUND: &str = "ERR_REWARD_SCHEDULE_NOT_FOUND";
+pub const ERR_INVALID_BUDGETS: &str = "ERR_INVALID_BUDGETS";
+pub const ERR_BUDGET_EXCEEDED: &str = "ERR_BUDGET_EXCEEDED";
+pub const ERR_COUNCIL_MEMBER_NOT_FOUND: &str = "ERR_COUNCIL_MEMBER_NOT_FOUND";
+pub const ERR_ALREADY_COUNCIL_MEMBER: &str = "ERR_ALREADY_COUNCIL_MEMBER";
+pub const ERR_COUNCIL_EMPTY: &str = "ERR_COUNCIL_EMPTY";
+pub const ERR_INVALID_POLICY: &str = "ERR_INVALID_POLICY";
+pub const ERR_NOT_A_MEMBER: &str = "ERR_NOT_A_MEMBER";
+pub const ERR_NOT_ENOUGH_BOND: &str = "ERR_NOT_ENOUGH_BOND";
+pub const ERR_VETO_REASON_REQUIRED: &str = "ERR_VETO_REASON_REQUIRED";
+pub const ERR_SPAM_REASON_REQUIRED: &str = "ERR_SPAM_REASON_REQUIRED";
+pub const ERR_INVALID_DELEGATION: &str = "ERR_INVALID_DELEGATION";
+pub const ERR_DELEGATION_NOT_FOUND: &str = "ERR_DELEGATION_NOT_FOUND";
+pub const ERR_INVALID_PRIZE_TABLE: &str = "ERR_INVALID_PRIZE_TABLE";
+pub const ERR_WINNERS_DO_NOT_MATCH_PRIZES: &str = "ERR_WINNERS_DO_NOT_MATCH_PRIZES";
+pub const ERR_PROPOSAL_KIND_MISMATCH: &str = "ERR_PROPOSAL_KIND_MISMATCH";
+pub const ERR_PROPOSAL_ID_REQUIRED: &str = "ERR_PROPOSAL_ID_REQUIRED";
+pub const ERR_PROPOSAL_NOT_APPROVED: &str = "ERR_PROPOSAL_NOT_APPROVED";
+pub const ERR_PROPOSAL_ALREADY_CLAIMED: &str = "ERR_PROPOSAL_ALREADY_CLAIMED";
+pub const ERR_COMPLETION_EXCEEDS_BUDGET: &str = "ERR_COMPLETION_EXCEEDS_BUDGET";
+pub const ERR_INVALID_MILESTONES: &str = "ERR_INVALID_MILESTONES";
+pub const ERR_MILESTONE_NOT_FOUND: &str = "ERR_MILESTONE_NOT_FOUND";
+pub const ERR_MILESTONE_ALREADY_REPORTED: &str = "ERR_MILESTONE_ALREADY_REPORTED";
+pub const ERR_OPEN_BOUNTY_NOT_FOUND: &str = "ERR_OPEN_BOUNTY_NOT_FOUND";
+pub const ERR_INVALID_OPEN_BOUNTY: &str = "ERR_INVALID_OPEN_BOUNTY";
+pub const ERR_OPEN_BOUNTY_CLOSED: &str = "ERR_OPEN_BOUNTY_CLOSED";
+pub const ERR_OPEN_BOUNTY_FULLY_CLAIMED: &str = "ERR_OPEN_BOUNTY_FULLY_CLAIMED";
+pub const ERR_OPEN_BOUNTY_NOT_CLAIMED: &str = "ERR_OPEN_BOUNTY_NOT_CLAIMED";
+pub const ERR_OPEN_BOUNTY_ALREADY_SUBMITTED: &str = "ERR_OPEN_BOUNTY_ALREADY_SUBMITTED";
+pub const ERR_INVALID_AMENDMENT: &str = "ERR_INVALID_AMENDMENT";
+pub const ERR_TOO_MANY_REVISIONS: &str = "ERR_TOO_MANY_REVISIONS";
+pub const ERR_REJECTION_REASON_REQUIRED: &str = "ERR_REJECTION_REASON_REQUIRED";
+pub const ERR_APPEAL_REASON_REQUIRED: &str = "ERR_APPEAL_REASON_REQUIRED";
+pub const ERR_ALREADY_APPEALED: &str = "ERR_ALREADY_APPEALED";
+pub const ERR_INVALID_COMMENT: &str = "ERR_INVALID_COMMENT";
+pub const ERR_COMMENT_THREAD_FULL: &str = "ERR_COMMENT_THREAD_FULL";
+pub const ERR_NOT_ENOUGH_DEPOSIT: &str = "ERR_NOT_ENOUGH_DEPOSIT";
@@ -1,23 +1,39 @@
 //! Contains the Contract struct and its implementation
 
+use std::collections::HashMap;
+
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::collections::LookupMap;
 use near_sdk::json_types::Base58CryptoHash;
-use near_sdk::{env, ext_contract, near_bindgen, serde_json::json, sys};
+use near_sdk::{env, ext_contract, near_bindgen, sys};
 use near_sdk::{AccountId, CryptoHash, Gas, PanicOnDefault, Promise};
-use std::str::FromStr;
 
 use ran::*;
 
+use amounts::RewardSchedule;
+use asset::PayoutAssets;
+use budget::{BudgetUsage, Budgets};
+use delegation::Delegation;
 use members::Members;
-use payout::PayoutInput;
+use migration::PayoutMigration;
+use oracle::{OracleConfig, OracleRate};
 use payout::{BountyPayout, MiscellaneousPayout, Payout, ProposalPayout, Referral, ReferralPayout};
+use payout::{
+    Comment, CompletionClaim, GovernancePayout, OpenBounty, PayoutInput, PayoutKind, Tranche,
+};
+use policy::{Policy, VotePolicy};
 use types::{usd_to_balance, Config, ReferralToken, RegistrationResult, USD};
 
 mod amounts;
+mod asset;
+mod budget;
+mod delegation;
 mod error;
 mod members;
+mod migration;
+mod oracle;
 mod payout;
+mod policy;
 mod types;
 mod upgrade;
 mod validation;
@@ -30,8 +46,7 @@ pub mod views;
 
 #[ext_contract(ext)]
 pub trait CrossContract {
-    fn get_exchange_rate(&self) -> f64;
-    fn make_transfers(&self, transfers: Vec<(AccountId, USD)>, #[callback_unwrap] cur: f64);
+    fn make_transfers(&mut self, kind: PayoutKind, id: u64, scheduled_at: u64, provider_index: u64);
 }
 
 /// The main contract governing Ambassadors DAO
@@ -42,6 +57,10 @@ pub struct Contract {
     pub members: Members,
     /// the configuration of the contract
     pub config: Config,
+    /// the voting policy of the contract
+    pub vote_policy: VotePolicy,
+    /// the roles of the members and the actions they can take
+    pub policy: Policy,
     /// proposal payouts
     pub proposals: LookupMap<u64, ProposalPayout>,
     /// the id of the last proposal
@@ -58,18 +77,54 @@ pub struct Contract {
     pub referrals: LookupMap<u64, ReferralPayout>,
     /// the id of the last referral
     pub last_referral_id: u64,
+    /// governance proposals
+    pub governance_proposals: LookupMap<u64, GovernancePayout>,
+    /// the id of the last governance proposal
+    pub last_governance_proposal_id: u64,
     /// referral tokens hash map
     pub referral_tokens: LookupMap<ReferralToken, AccountId>,
     /// Large blob storage.
     pub blobs: LookupMap<CryptoHash, AccountId>,
-    /// What oracle is the contract using
-    pub oracle: AccountId,
+    /// the oracles providing the exchange rate and the bounds of the rate
+    pub oracle: OracleConfig,
+    /// the assets payouts are paid in
+    pub payout_assets: PayoutAssets,
+    /// every version of the reward schedule
+    pub reward_schedules: LookupMap<u64, RewardSchedule>,
+    /// the version of the current reward schedule
+    pub reward_schedule_version: u64,
+    /// the version of the council, incremented whenever a member is added or removed
+    pub council_version: u64,
+    /// the delegation of the vote of each council member
+    pub delegations: HashMap<AccountId, Delegation>,
+    /// the budget caps per kind of payout
+    pub budgets: Budgets,
+    /// the amounts approved per kind of payout in the current periods
+    pub budget_usage: HashMap<PayoutKind, BudgetUsage>,
+    /// the completion bounty claiming each hackathon and meme contest proposal
+    pub completion_claims: LookupMap<u64, CompletionClaim>,
+    /// the tranches of the budget of each approved proposal with milestones
+    pub tranches: LookupMap<u64, Vec<Tranche>>,
+    /// the bounties published on the board
+    pub open_bounties: LookupMap<u64, OpenBounty>,
+    /// the id of the last bounty published on the board
+    pub last_open_bounty_id: u64,
+    /// the comment thread of each payout
+    pub comments: LookupMap<(PayoutKind, u64), Vec<Comment>>,
+    /// the payouts of each kind still stored in the format of version 0.2.0
+    pub payout_migrations: HashMap<PayoutKind, PayoutMigration>,
 }
 
 #[near_bindgen]
 impl Contract {
+    /// The oracle defaults to the NEAR oracle of the network the contract is deployed on
     #[init]
-    pub fn new(name: String, purpose: String, council: Vec<AccountId>) -> Self {
+    pub fn new(
+        name: String,
+        purpose: String,
+        council: Vec<AccountId>,
+        oracle: Option<OracleConfig>,
+    ) -> Self {
         if name.is_empty() {
             panic!("ERR_INVALID_NAME");
         }
@@ -77,16 +132,20 @@ impl Contract {
             panic!("ERR_PURPOSE_EMPTY");
         }
         if council.is_empty() {
-            panic!("ERR_COUNCIL_EMPTY");
+            panic!("{}", error::ERR_COUNCIL_EMPTY);
         }
+        let oracle = oracle.unwrap_or_default();
+        oracle.assert_valid();
         set_seeds(
             env::random_seed()
                 .into_iter()
                 .fold(0_u64, |acc, x| acc + (x as u64 * x as u64)),
         );
-        Self {
+        let mut contract = Self {
             members: Members::from_council(council),
             config: Config::new(name, purpose),
+            vote_policy: VotePolicy::default(),
+            policy: Policy::default(),
             proposals: LookupMap::new(b"p".to_vec()),
             last_proposal_id: 0,
             bounties: LookupMap::new(b"b".to_vec()),
@@ -95,25 +154,35 @@ impl Contract {
             last_miscellaneous_id: 0,
             referrals: LookupMap::new(b"r".to_vec()),
             last_referral_id: 0,
+            governance_proposals: LookupMap::new(b"g".to_vec()),
+            last_governance_proposal_id: 0,
             referral_tokens: LookupMap::new(b"t".to_vec()),
             blobs: LookupMap::new(b"l".to_vec()),
-            oracle: Self::get_oracle(),
-        }
-    }
-
-    #[private]
-    pub fn get_oracle() -> AccountId {
-        if env::current_account_id().as_str().ends_with(".near") {
-            AccountId::from_str("v1.noracle.near").unwrap()
-        } else {
-            AccountId::from_str("v1.nearacle.testnet").unwrap()
-        }
+            oracle,
+            payout_assets: PayoutAssets::default(),
+            reward_schedules: LookupMap::new(b"s".to_vec()),
+            reward_schedule_version: 0,
+            council_version: 0,
+            delegations: HashMap::new(),
+            budgets: Budgets::default(),
+            budget_usage: HashMap::new(),
+            completion_claims: LookupMap::new(b"c".to_vec()),
+            tranches: LookupMap::new(b"e".to_vec()),
+            open_bounties: LookupMap::new(b"o".to_vec()),
+            last_open_bounty_id: 0,
+            comments: LookupMap::new(b"d".to_vec()),
+            payout_migrations: HashMap::new(),
+        };
+        contract
+            .reward_schedules
+            .insert(&0, &RewardSchedule::default());
+        contract
     }
 
     /// Should only be called by this contract on migration.
-    /// This is NOOP implementation. KEEP IT if you haven't changed contract state.
-    /// If you have changed state, you need to implement migration from old state (keep the old struct with different name to deserialize it first).
-    /// After migrate goes live on MainNet, return this implementation for next updates.
+    /// Migrates the state of version 0.2.0, see the `migration` module.
+    /// After migrate goes live on MainNet, return the NOOP implementation for next updates:
+    /// `env::state_read::<Contract>().expect(error::ERR_CONTRACT_NOT_INITIALIZED)`
     #[init(ignore_state)]
     pub fn migrate() -> Self {
         assert_eq!(
@@ -122,28 +191,7 @@ impl Contract {
             "{}",
             error::ERR_NOT_PERMITTED
         );
-        env::state_read::<Contract>().expect(error::ERR_CONTRACT_NOT_INITIALIZED)
-    }
-
-    #[private]
-    pub fn get_exchange_rate(&self) -> Promise {
-        Promise::new(self.oracle.clone()).function_call(
-            "get_rate".to_string(),
-            json!({
-                "currency":"NEAR",
-            })
-            .to_string()
-            .into(),
-            0_u128,
-            Gas(types::ONE_TGAS * 10),
-        )
-    }
-
-    #[private]
-    pub fn make_transfers(&self, transfers: Vec<(AccountId, USD)>, #[callback_unwrap] rate: f64) {
-        for (payee, usd_amount) in transfers {
-            Promise::new(payee).transfer(usd_to_balance(usd_amount, rate));
-        }
+        Self::internal_migrate_from_v0_2_0()
     }
 
     /// Perform required actions when an ambassador registers
@@ -171,13 +219,17 @@ impl Contract {
                             self.members
                                 .add_ambassador(signer.clone(), ref_token.clone(), true);
                         // add payout record
-                        self.add_payout_referral(PayoutInput::<Referral> {
-                            description: "Ambassador registration referral".to_string(),
-                            information: Referral::AmbassadorRegistration {
-                                referrer_id: signer,
-                                referred_id: id,
+                        // no bond is taken for referrals created on registration
+                        self.internal_add_payout_referral(
+                            PayoutInput::<Referral> {
+                                description: "Ambassador registration referral".to_string(),
+                                information: Referral::AmbassadorRegistration {
+                                    referrer_id: signer,
+                                    referred_id: id,
+                                },
                             },
-                        });
+                            0,
+                        );
                         RegistrationResult::SuccessWithReferral(new_id)
                     } else {
                         let new_id =
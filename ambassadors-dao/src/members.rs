@@ -40,7 +40,7 @@ impl Members {
     /// creates a new members struct with given council ids and referral tokens
     pub fn from_council(input: Vec<AccountId>) -> Self {
         Members {
-            council: HashSet::from_iter(input.into_iter()),
+            council: HashSet::from_iter(input),
             last_ambassador_id: 0,
             ambassadors: HashMap::new(),
         }
@@ -105,28 +105,4 @@ impl Contract {
             .expect(error::ERR_AMBASSADOR_NOT_FOUND)
             .clone()
     }
-
-    /// Add an account ID to the council
-    /// Can only be done by the smart contract owner
-    pub fn add_council_member(&mut self, account_id: AccountId) {
-        assert_eq!(
-            env::current_account_id(),
-            env::signer_account_id(),
-            "{}",
-            error::ERR_NOT_PERMITTED,
-        );
-        self.members.council.insert(account_id);
-    }
-
-    /// Remove an account ID from the council
-    /// Can only be done by the smart contract owner
-    pub fn remove_council_member(&mut self, account_id: AccountId) {
-        assert_eq!(
-            env::current_account_id(),
-            env::signer_account_id(),
-            "{}",
-            error::ERR_NOT_PERMITTED,
-        );
-        self.members.council.remove(&account_id);
-    }
 }
@@ -0,0 +1,491 @@
+//! Migration of the contract state from version 0.2.0, in which USD amounts
+//! were stored as `f64`, payouts had no submission time, payments or bond, and
+//! the config had no submission bond.
+//!
+//! `migrate` only converts the top level state. The stored payouts are then
+//! rewritten in batches with `migrate_payouts`, so that no single call has to
+//! rewrite every payout.
+
+use std::collections::HashMap;
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
+use near_sdk::json_types::{U128, U64};
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::{env, near_bindgen, AccountId, CryptoHash};
+
+use crate::amounts::RewardSchedule;
+use crate::asset::PayoutAssets;
+use crate::budget::Budgets;
+use crate::members::Members;
+use crate::oracle::{OracleConfig, OracleProvider, RateLimits};
+use crate::payout::{
+    Bounty, Miscellaneous, Payout, PayoutKind, PayoutStatus, Proposal, Referral, ResourceLink,
+    SubmissionInfo, VotesCount,
+};
+use crate::types::{Config, ReferralToken, USD};
+use crate::vote::VoteRecord;
+use crate::*;
+
+/// The payouts of a kind still stored in the format of version 0.2.0
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PayoutMigration {
+    /// the id of the next payout to rewrite
+    pub next_id: u64,
+    /// the id of the last payout submitted before the migration
+    pub last_id: u64,
+}
+
+#[derive(BorshDeserialize)]
+pub struct OldContract {
+    pub members: Members,
+    pub config: OldConfig,
+    // the payout collections are only read for the layout, the payouts are
+    // rewritten by `migrate_payouts` under the same prefixes
+    pub _proposals: LookupMap<u64, OldPayout<OldProposal>>,
+    pub last_proposal_id: u64,
+    pub _bounties: LookupMap<u64, OldPayout<OldBounty>>,
+    pub last_bounty_id: u64,
+    pub _miscellaneous: LookupMap<u64, OldPayout<OldMiscellaneous>>,
+    pub last_miscellaneous_id: u64,
+    pub _referrals: LookupMap<u64, OldPayout<Referral>>,
+    pub last_referral_id: u64,
+    pub referral_tokens: LookupMap<ReferralToken, AccountId>,
+    pub blobs: LookupMap<CryptoHash, AccountId>,
+    pub oracle: AccountId,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct OldConfig {
+    pub name: String,
+    pub purpose: String,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct OldPayout<T> {
+    pub status: PayoutStatus,
+    pub proposer: AccountId,
+    pub info: T,
+    pub description: String,
+    pub votes: HashMap<AccountId, vote::Vote>,
+    pub votes_count: OldVotesCount,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub struct OldVotesCount {
+    pub approve_count: u64,
+    pub reject_count: u64,
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum OldProposal {
+    Hackathon {
+        expected_registrations: u64,
+        estimated_budget: f64,
+        supporting_document: ResourceLink,
+    },
+    MemeContest {
+        expected_registrations: u64,
+        estimated_budget: f64,
+        supporting_document: ResourceLink,
+    },
+    Open {
+        estimated_budget: f64,
+        supporting_document: ResourceLink,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum OldBounty {
+    HackathonCompletion {
+        num_of_registrations: u64,
+        num_of_submissions: u64,
+        winners_info: Vec<SubmissionInfo>,
+    },
+    MemeContestCompletion {
+        num_of_registrations: u64,
+        num_of_submissions: u64,
+        winners_info: Vec<SubmissionInfo>,
+    },
+    Webinar {
+        num_of_registrations: u64,
+        num_of_attendees: u64,
+        webinar_link: ResourceLink,
+    },
+    ContentCoordination {
+        content_links: Vec<ResourceLink>,
+        story: String,
+        tools_used: Vec<String>,
+    },
+}
+
+#[derive(BorshSerialize, BorshDeserialize)]
+pub enum OldMiscellaneous {
+    ContentCreationBounty {
+        links_to_content: Vec<ResourceLink>,
+        expected_amount: f64,
+        note: String,
+    },
+    CampusSigningMOU {
+        supporting_document: ResourceLink,
+    },
+    CampusAmbassadorBonus {
+        links_to_payouts: Vec<ResourceLink>,
+    },
+}
+
+impl From<OldProposal> for Proposal {
+    fn from(old: OldProposal) -> Self {
+        match old {
+            OldProposal::Hackathon {
+                expected_registrations,
+                estimated_budget,
+                supporting_document,
+            } => Proposal::Hackathon {
+                expected_registrations,
+                estimated_budget: USD::from_f64(estimated_budget),
+                supporting_document,
+                prize_table: None,
+                milestones: None,
+            },
+            OldProposal::MemeContest {
+                expected_registrations,
+                estimated_budget,
+                supporting_document,
+            } => Proposal::MemeContest {
+                expected_registrations,
+                estimated_budget: USD::from_f64(estimated_budget),
+                supporting_document,
+                prize_table: None,
+                milestones: None,
+            },
+            OldProposal::Open {
+                estimated_budget,
+                supporting_document,
+            } => Proposal::Open {
+                estimated_budget: USD::from_f64(estimated_budget),
+                supporting_document,
+                milestones: None,
+            },
+        }
+    }
+}
+
+/// Completion bounties of 0.2.0 were paid with the prizes of the reward schedule.
+impl From<OldBounty> for Bounty {
+    fn from(old: OldBounty) -> Self {
+        match old {
+            OldBounty::HackathonCompletion {
+                num_of_registrations,
+                num_of_submissions,
+                winners_info,
+            } => Bounty::HackathonCompletion {
+                num_of_registrations,
+                num_of_submissions,
+                winners_info,
+                proposal_id: None,
+            },
+            OldBounty::MemeContestCompletion {
+                num_of_registrations,
+                num_of_submissions,
+                winners_info,
+            } => Bounty::MemeContestCompletion {
+                num_of_registrations,
+                num_of_submissions,
+                winners_info,
+                proposal_id: None,
+            },
+            OldBounty::Webinar {
+                num_of_registrations,
+                num_of_attendees,
+                webinar_link,
+            } => Bounty::Webinar {
+                num_of_registrations,
+                num_of_attendees,
+                webinar_link,
+            },
+            OldBounty::ContentCoordination {
+                content_links,
+                story,
+                tools_used,
+            } => Bounty::ContentCoordination {
+                content_links,
+                story,
+                tools_used,
+            },
+        }
+    }
+}
+
+impl From<OldMiscellaneous> for Miscellaneous {
+    fn from(old: OldMiscellaneous) -> Self {
+        match old {
+            OldMiscellaneous::ContentCreationBounty {
+                links_to_content,
+                expected_amount,
+                note,
+            } => Miscellaneous::ContentCreationBounty {
+                links_to_content,
+                expected_amount: USD::from_f64(expected_amount),
+                note,
+            },
+            OldMiscellaneous::CampusSigningMOU {
+                supporting_document,
+            } => Miscellaneous::CampusSigningMOU {
+                supporting_document,
+            },
+            OldMiscellaneous::CampusAmbassadorBonus { links_to_payouts } => {
+                Miscellaneous::CampusAmbassadorBonus { links_to_payouts }
+            }
+        }
+    }
+}
+
+/// Payouts under consideration get a new voting period from the migration.
+/// The transfers of payouts approved before the migration were not recorded,
+/// so they get no payments and can never be retried.
+impl<O, T: Serialize + From<O>> From<OldPayout<O>> for Payout<T> {
+    fn from(old: OldPayout<O>) -> Self {
+        Self {
+            status: old.status,
+            proposer: old.proposer,
+            info: T::from(old.info),
+            description: old.description,
+            votes: old
+                .votes
+                .into_iter()
+                .map(|(voter, vote)| {
+                    (
+                        voter,
+                        VoteRecord {
+                            vote,
+                            delegate: None,
+                            comment: None,
+                        },
+                    )
+                })
+                .collect(),
+            votes_count: VotesCount {
+                approve_count: old.votes_count.approve_count,
+                reject_count: old.votes_count.reject_count,
+                abstain_count: 0,
+            },
+            submission_time: U64(env::block_timestamp()),
+            payments: Vec::new(),
+            reward_schedule_version: 0,
+            over_budget: false,
+            bond: U128(0),
+            vetoes: HashMap::new(),
+            council_version: 0,
+            revisions: Vec::new(),
+            appeal: None,
+        }
+    }
+}
+
+/// rewrite up to `limit` payouts of a collection in the new format, in place,
+/// starting from the next payout of the migration
+fn migrate_payouts<O, T>(
+    prefix: &[u8],
+    collection: &mut LookupMap<u64, Payout<T>>,
+    migration: &mut PayoutMigration,
+    limit: u64,
+) where
+    O: BorshSerialize + BorshDeserialize,
+    T: Serialize + BorshSerialize + BorshDeserialize + From<O>,
+{
+    // the old payouts are stored under the same prefix as the new ones
+    let mut old: LookupMap<u64, OldPayout<O>> = LookupMap::new(prefix.to_vec());
+    let end = std::cmp::min(
+        migration.next_id.saturating_add(limit),
+        migration.last_id + 1,
+    );
+    for id in migration.next_id..end {
+        // removing the old payout first, so that inserting does not read it back
+        // in the new format
+        if let Some(payout) = old.remove(&id) {
+            collection.insert(&id, &Payout::from(payout));
+        }
+    }
+    migration.next_id = end;
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Rewrite up to `limit` payouts of a kind stored in the format of version
+    /// 0.2.0. Can be called by anyone until every payout is rewritten, returns
+    /// the number of payouts of the kind left to rewrite.
+    pub fn migrate_payouts(&mut self, kind: PayoutKind, limit: u64) -> u64 {
+        let mut migration = self
+            .payout_migrations
+            .remove(&kind)
+            .unwrap_or_else(|| panic!("{}", error::ERR_NOTHING_TO_MIGRATE));
+        match kind {
+            PayoutKind::Proposal => {
+                migrate_payouts::<OldProposal, _>(b"p", &mut self.proposals, &mut migration, limit)
+            }
+            PayoutKind::Bounty => {
+                migrate_payouts::<OldBounty, _>(b"b", &mut self.bounties, &mut migration, limit)
+            }
+            PayoutKind::Miscellaneous => migrate_payouts::<OldMiscellaneous, _>(
+                b"m",
+                &mut self.miscellaneous,
+                &mut migration,
+                limit,
+            ),
+            PayoutKind::Referral => {
+                migrate_payouts::<Referral, _>(b"r", &mut self.referrals, &mut migration, limit)
+            }
+            PayoutKind::Governance => panic!("{}", error::ERR_NOTHING_TO_MIGRATE),
+        }
+        let left = migration.last_id + 1 - migration.next_id;
+        if left > 0 {
+            self.payout_migrations.insert(kind, migration);
+        }
+        left
+    }
+
+    /// Get the payouts of each kind still stored in the format of version 0.2.0
+    pub fn get_payout_migrations(&self) -> HashMap<PayoutKind, PayoutMigration> {
+        self.payout_migrations.clone()
+    }
+}
+
+impl Contract {
+    /// Read the state of version 0.2.0. The stored payouts are left in the old
+    /// format and rewritten later with `migrate_payouts`.
+    pub(crate) fn internal_migrate_from_v0_2_0() -> Self {
+        let old: OldContract = env::state_read().expect(error::ERR_CONTRACT_NOT_INITIALIZED);
+        let mut contract = Self {
+            members: old.members,
+            config: Config::new(old.config.name, old.config.purpose),
+            vote_policy: VotePolicy::default(),
+            policy: Policy::default(),
+            proposals: LookupMap::new(b"p".to_vec()),
+            last_proposal_id: old.last_proposal_id,
+            bounties: LookupMap::new(b"b".to_vec()),
+            last_bounty_id: old.last_bounty_id,
+            miscellaneous: LookupMap::new(b"m".to_vec()),
+            last_miscellaneous_id: old.last_miscellaneous_id,
+            referrals: LookupMap::new(b"r".to_vec()),
+            last_referral_id: old.last_referral_id,
+            governance_proposals: LookupMap::new(b"g".to_vec()),
+            last_governance_proposal_id: 0,
+            referral_tokens: old.referral_tokens,
+            blobs: old.blobs,
+            oracle: OracleConfig {
+                providers: vec![OracleProvider {
+                    account_id: old.oracle,
+                    ..OracleConfig::default().providers.remove(0)
+                }],
+                rate_limits: RateLimits::default(),
+            },
+            payout_assets: PayoutAssets::default(),
+            reward_schedules: LookupMap::new(b"s".to_vec()),
+            reward_schedule_version: 0,
+            council_version: 0,
+            delegations: HashMap::new(),
+            budgets: Budgets::default(),
+            budget_usage: HashMap::new(),
+            completion_claims: LookupMap::new(b"c".to_vec()),
+            tranches: LookupMap::new(b"e".to_vec()),
+            open_bounties: LookupMap::new(b"o".to_vec()),
+            last_open_bounty_id: 0,
+            comments: LookupMap::new(b"d".to_vec()),
+            payout_migrations: [
+                (PayoutKind::Proposal, old.last_proposal_id),
+                (PayoutKind::Bounty, old.last_bounty_id),
+                (PayoutKind::Miscellaneous, old.last_miscellaneous_id),
+                (PayoutKind::Referral, old.last_referral_id),
+            ]
+            .into_iter()
+            .filter(|(_, last_id)| *last_id > 0)
+            .map(|(kind, last_id)| {
+                (
+                    kind,
+                    PayoutMigration {
+                        next_id: 1,
+                        last_id,
+                    },
+                )
+            })
+            .collect(),
+        };
+        // the amounts of version 0.2.0 are the first version of the schedule
+        contract
+            .reward_schedules
+            .insert(&0, &RewardSchedule::default());
+        contract
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    /// a contract whose 3 proposals are stored in the format of version 0.2.0
+    fn contract_with_old_proposals() -> Contract {
+        testing_env!(VMContextBuilder::new().build());
+        let mut contract = Contract::new(
+            "dao".to_string(),
+            "testing".to_string(),
+            vec![accounts(0)],
+            None,
+        );
+        let mut old: LookupMap<u64, OldPayout<OldProposal>> = LookupMap::new(b"p".to_vec());
+        for id in 1..=3 {
+            old.insert(
+                &id,
+                &OldPayout {
+                    status: PayoutStatus::UnderConsideration,
+                    proposer: accounts(1),
+                    info: OldProposal::Open {
+                        estimated_budget: 1.5,
+                        supporting_document: "https://example.com/doc.pdf".to_string(),
+                    },
+                    description: "proposal".to_string(),
+                    votes: HashMap::new(),
+                    votes_count: OldVotesCount {
+                        approve_count: 0,
+                        reject_count: 0,
+                    },
+                },
+            );
+        }
+        contract.last_proposal_id = 3;
+        contract.payout_migrations.insert(
+            PayoutKind::Proposal,
+            PayoutMigration {
+                next_id: 1,
+                last_id: 3,
+            },
+        );
+        contract
+    }
+
+    #[test]
+    fn payouts_are_migrated_in_batches() {
+        let mut contract = contract_with_old_proposals();
+        assert_eq!(contract.migrate_payouts(PayoutKind::Proposal, 2), 1);
+        assert!(contract.proposals.get(&2).is_some());
+        assert_eq!(contract.migrate_payouts(PayoutKind::Proposal, 2), 0);
+        assert!(contract.get_payout_migrations().is_empty());
+        let payout = contract.proposals.get(&3).unwrap();
+        assert!(matches!(
+            payout.info,
+            Proposal::Open { estimated_budget, .. } if estimated_budget == USD::from_f64(1.5)
+        ));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOTHING_TO_MIGRATE")]
+    fn payouts_are_never_migrated_twice() {
+        let mut contract = contract_with_old_proposals();
+        contract.migrate_payouts(PayoutKind::Proposal, 3);
+        contract.migrate_payouts(PayoutKind::Proposal, 3);
+    }
+}
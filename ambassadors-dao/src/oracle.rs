@@ -0,0 +1,272 @@
+//! Contains the configuration of the oracles providing the exchange rate
+//! and the validation of the exchange rate they return
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::serde_json::{self, json, Value};
+
+use crate::payout::{GAS_FOR_MAKE_TRANSFERS, GAS_FOR_ON_PAYMENT_TRANSFERRED};
+use crate::*;
+
+/// Ten minutes in nanoseconds
+pub const DEFAULT_MAX_RATE_AGE: u64 = 10 * 60 * 1_000_000_000;
+/// Maximum gas that can be attached to the call of an oracle
+pub const MAX_GAS_FOR_ORACLE: u64 = types::ONE_TGAS * 50;
+/// Maximum number of oracles that can be configured
+pub const MAX_ORACLE_PROVIDERS: usize = 3;
+/// Maximum gas for querying every oracle in turn, the calls of the oracles and
+/// of make_transfers after each of them, leaving the rest of the transaction
+/// for the transfers
+pub const MAX_GAS_FOR_ORACLES: u64 = types::ONE_TGAS * 100;
+
+/// The response of the oracle, either a bare rate or a rate with the
+/// timestamp in nanoseconds at which it was observed
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde", untagged)]
+pub enum OracleRate {
+    Rate(f64),
+    WithTimestamp { rate: f64, timestamp: U64 },
+}
+
+/// The bounds an exchange rate must be within to be used for payments
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RateLimits {
+    /// the lowest accepted rate in dollars per NEAR
+    pub min_rate: USD,
+    /// the highest accepted rate in dollars per NEAR
+    pub max_rate: USD,
+    /// the maximum age in nanoseconds of a rate returned with a timestamp
+    pub max_age: Option<U64>,
+}
+
+impl RateLimits {
+    /// check the limits are well formed
+    pub fn assert_valid(&self) {
+        if self.min_rate.0 == 0 || self.min_rate > self.max_rate {
+            panic!("{}", error::ERR_INVALID_RATE_LIMITS);
+        }
+    }
+
+    /// Returns the rate rounded to the micro-dollar, or the reason it was rejected.
+    pub fn validate(&self, response: &OracleRate) -> Result<USD, String> {
+        let (rate, timestamp) = match response {
+            OracleRate::Rate(rate) => (*rate, None),
+            OracleRate::WithTimestamp { rate, timestamp } => (*rate, Some(timestamp.0)),
+        };
+        if !rate.is_finite() || rate <= 0. {
+            return Err(format!("invalid exchange rate {}", rate));
+        }
+        let rate = USD::from_f64(rate);
+        if rate < self.min_rate || rate > self.max_rate {
+            return Err(format!("exchange rate {} out of bounds", rate));
+        }
+        if let (Some(timestamp), Some(max_age)) = (timestamp, &self.max_age) {
+            if env::block_timestamp().saturating_sub(timestamp) > max_age.0 {
+                return Err(format!("exchange rate observed at {} is stale", timestamp));
+            }
+        }
+        Ok(rate)
+    }
+}
+
+impl Default for RateLimits {
+    fn default() -> Self {
+        Self {
+            min_rate: USD(100_000),
+            max_rate: USD::from_dollars(1_000),
+            max_age: Some(U64(DEFAULT_MAX_RATE_AGE)),
+        }
+    }
+}
+
+/// An oracle that can be called for the exchange rate of NEAR in dollars
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleProvider {
+    /// the account of the oracle contract
+    pub account_id: AccountId,
+    /// the method returning the exchange rate
+    pub method_name: String,
+    /// the JSON arguments of the method
+    pub args: String,
+    /// the gas attached to the call
+    pub gas: U64,
+}
+
+impl OracleProvider {
+    /// check the provider is well formed
+    pub fn assert_valid(&self) {
+        if self.method_name.is_empty()
+            || self.gas.0 == 0
+            || self.gas.0 > MAX_GAS_FOR_ORACLE
+            || serde_json::from_str::<Value>(&self.args).is_err()
+        {
+            panic!("{}", error::ERR_INVALID_ORACLE_CONFIG);
+        }
+    }
+}
+
+/// The oracles used for the exchange rate and the bounds of the rate
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OracleConfig {
+    /// the oracles in the order they are tried, the first one is the primary oracle
+    /// and the next ones are tried when the previous one fails or returns a rejected rate
+    pub providers: Vec<OracleProvider>,
+    /// the bounds of the exchange rate
+    pub rate_limits: RateLimits,
+}
+
+impl OracleConfig {
+    /// check the configuration is well formed and querying every oracle in
+    /// turn fits in the gas of a transaction
+    pub fn assert_valid(&self) {
+        if self.providers.is_empty() || self.providers.len() > MAX_ORACLE_PROVIDERS {
+            panic!("{}", error::ERR_INVALID_ORACLE_CONFIG);
+        }
+        self.providers.iter().for_each(OracleProvider::assert_valid);
+        let gas_for_oracles: u64 = self
+            .providers
+            .iter()
+            .map(|provider| provider.gas.0 + GAS_FOR_MAKE_TRANSFERS)
+            .sum();
+        if gas_for_oracles > MAX_GAS_FOR_ORACLES {
+            panic!("{}", error::ERR_INVALID_ORACLE_CONFIG);
+        }
+        self.rate_limits.assert_valid();
+    }
+}
+
+impl Default for OracleConfig {
+    /// Uses the NEAR oracle of the network the contract is deployed on.
+    fn default() -> Self {
+        let account_id = if env::current_account_id().as_str().ends_with(".near") {
+            "v1.noracle.near"
+        } else {
+            "v1.nearacle.testnet"
+        };
+        Self {
+            providers: vec![OracleProvider {
+                account_id: account_id.parse().unwrap(),
+                method_name: "get_rate".to_string(),
+                args: json!({
+                    "currency":"NEAR",
+                })
+                .to_string(),
+                gas: U64(types::ONE_TGAS * 10),
+            }],
+            rate_limits: RateLimits::default(),
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the oracle configuration of this contract.
+    pub fn get_oracle_config(&self) -> OracleConfig {
+        self.oracle.clone()
+    }
+}
+
+impl Contract {
+    /// Query an oracle for the exchange rate and make the transfers scheduled
+    /// at the given block with it, the next oracles are tried if it fails.
+    pub(crate) fn internal_query_oracle(
+        &self,
+        provider_index: u64,
+        kind: PayoutKind,
+        id: u64,
+        scheduled_at: u64,
+        num_of_transfers: u64,
+    ) -> Promise {
+        let provider = &self.oracle.providers[provider_index as usize];
+        Promise::new(provider.account_id.clone())
+            .function_call(
+                provider.method_name.clone(),
+                provider.args.clone().into_bytes(),
+                0,
+                Gas(provider.gas.0),
+            )
+            .then(ext::make_transfers(
+                kind,
+                id,
+                scheduled_at,
+                provider_index,
+                env::current_account_id(),
+                0,
+                self.internal_gas_for_make_transfers(provider_index, num_of_transfers),
+            ))
+    }
+
+    /// The gas make_transfers needs to either make the transfers, or to query
+    /// the next oracle and call make_transfers again.
+    fn internal_gas_for_make_transfers(&self, provider_index: u64, num_of_transfers: u64) -> Gas {
+        let for_transfers = GAS_FOR_ON_PAYMENT_TRANSFERRED * num_of_transfers;
+        let for_fallback = match self.oracle.providers.get(provider_index as usize + 1) {
+            Some(next) => {
+                next.gas.0
+                    + self
+                        .internal_gas_for_make_transfers(provider_index + 1, num_of_transfers)
+                        .0
+            }
+            None => 0,
+        };
+        Gas(GAS_FOR_MAKE_TRANSFERS + for_transfers.max(for_fallback))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+
+    /// the default configuration with the given gas for each of the oracles
+    fn config(gas: &[u64]) -> OracleConfig {
+        testing_env!(VMContextBuilder::new().build());
+        let default = OracleConfig::default().providers.remove(0);
+        OracleConfig {
+            providers: gas
+                .iter()
+                .enumerate()
+                .map(|(index, gas)| OracleProvider {
+                    account_id: accounts(index),
+                    gas: U64(types::ONE_TGAS * gas),
+                    ..default.clone()
+                })
+                .collect(),
+            rate_limits: RateLimits::default(),
+        }
+    }
+
+    #[test]
+    fn rejects_invalid_rates() {
+        let limits = RateLimits::default();
+        assert_eq!(limits.validate(&OracleRate::Rate(4.25)), Ok(USD(4_250_000)));
+        assert!(limits.validate(&OracleRate::Rate(0.)).is_err());
+        assert!(limits.validate(&OracleRate::Rate(-1.)).is_err());
+        assert!(limits.validate(&OracleRate::Rate(f64::NAN)).is_err());
+        assert!(limits.validate(&OracleRate::Rate(1e9)).is_err());
+    }
+
+    #[test]
+    fn oracles_fit_in_a_transaction() {
+        config(&[10, 10, 10]).assert_valid();
+        config(&[50, 40]).assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_ORACLE_CONFIG")]
+    fn oracles_are_capped() {
+        config(&[10, 10, 10, 10]).assert_valid();
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_ORACLE_CONFIG")]
+    fn gas_for_oracles_is_capped() {
+        config(&[50, 50]).assert_valid();
+    }
+}
@@ -0,0 +1,148 @@
+use near_sdk::near_bindgen;
+use near_sdk::serde::de::DeserializeOwned;
+use near_sdk::serde_json::{self, Value};
+
+use super::*;
+
+/// The maximum number of times a payout can be amended
+pub const MAX_REVISIONS_PER_PAYOUT: usize = 10;
+
+/// A previous version of a payout, replaced by an amendment of its proposer
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Revision<T: Serialize> {
+    /// the description of the payout before the amendment
+    pub description: String,
+    /// the information of the payout before the amendment
+    pub info: T,
+    /// the block timestamp of the amendment
+    pub amended_at: U64,
+    /// if the votes cast before the amendment were reset
+    pub votes_reset: bool,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Replace the description and information of a payout under consideration
+    /// with a new input of its kind. Can only be done by the proposer, the new
+    /// input is validated as on submission and the previous version is kept in
+    /// the revisions of the payout, up to `MAX_REVISIONS_PER_PAYOUT` times. The
+    /// amended payout is paid with the current reward schedule, and the votes
+    /// and vetoes are reset unless the vote rule of the kind keeps them.
+    pub fn amend_payout(&mut self, kind: PayoutKind, id: u64, new_input: Value) {
+        match kind {
+            PayoutKind::Proposal => self.internal_amend_payout::<Proposal>(id, new_input),
+            PayoutKind::Bounty => self.internal_amend_payout::<Bounty>(id, new_input),
+            PayoutKind::Miscellaneous => self.internal_amend_payout::<Miscellaneous>(id, new_input),
+            PayoutKind::Referral => self.internal_amend_payout::<Referral>(id, new_input),
+            PayoutKind::Governance => {
+                self.internal_amend_payout::<GovernanceProposal>(id, new_input)
+            }
+        }
+    }
+}
+
+impl Contract {
+    fn internal_amend_payout<T: PayoutInfo + DeserializeOwned>(
+        &mut self,
+        id: u64,
+        new_input: Value,
+    ) {
+        let new_input: PayoutInput<T> = serde_json::from_value(new_input)
+            .unwrap_or_else(|_| panic!("{}", error::ERR_INVALID_AMENDMENT));
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        let rule = self.vote_policy.rule_for(&T::KIND).clone();
+        if !payout.is_pending(&rule) {
+            panic!(
+                "{}: {}",
+                error::ERR_NOT_PERMITTED,
+                "payout not under consideration"
+            );
+        }
+        // only the proposer can amend the payout
+        if payout.proposer != env::predecessor_account_id() {
+            panic!("{}", error::ERR_NOT_PERMITTED);
+        }
+        if payout.revisions.len() >= MAX_REVISIONS_PER_PAYOUT {
+            panic!("{}", error::ERR_TOO_MANY_REVISIONS);
+        }
+        // the previous version no longer claims what it linked to, so the new
+        // version can link to the same records
+        T::unlink(self, id, &payout.info);
+        T::validate(self, id, &new_input.information);
+
+        let votes_reset = !rule.keep_votes_on_amendment;
+        payout.revisions.push(Revision {
+            description: std::mem::replace(&mut payout.description, new_input.description),
+            info: std::mem::replace(&mut payout.info, new_input.information),
+            amended_at: U64(env::block_timestamp()),
+            votes_reset,
+        });
+        // the new version was validated against the current reward schedule
+        payout.reward_schedule_version = self.reward_schedule_version;
+        if votes_reset {
+            payout.votes.clear();
+            payout.votes_count = VotesCount::new();
+            payout.vetoes.clear();
+        }
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::serde_json::json;
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::payout::fixtures::{contract_with_proposal, open_proposal};
+
+    /// the input of an open proposal with the given budget
+    fn open_proposal_input(budget: u64) -> Value {
+        json!({
+            "description": "open proposal",
+            "information": {
+                "Open": {
+                    "estimated_budget": USD::from_dollars(budget),
+                    "supporting_document": "https://example.com/doc.pdf",
+                    "milestones": null,
+                }
+            }
+        })
+    }
+
+    /// a contract with a proposal of accounts(1) submitted with the first
+    /// reward schedule and vetoed by accounts(0)
+    fn contract_with_vetoed_proposal() -> Contract {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .build());
+        let mut payout = open_proposal();
+        payout
+            .vetoes
+            .insert(accounts(0), "not an event".to_string());
+        let mut contract = contract_with_proposal(payout);
+        contract.reward_schedule_version = 1;
+        contract
+    }
+
+    #[test]
+    fn amendment_uses_the_current_schedule_and_resets_vetoes() {
+        let mut contract = contract_with_vetoed_proposal();
+        contract.amend_payout(PayoutKind::Proposal, 1, open_proposal_input(20));
+        let payout = contract.proposals.get(&1).unwrap();
+        assert_eq!(payout.reward_schedule_version, 1);
+        assert!(payout.vetoes.is_empty());
+        assert_eq!(payout.revisions.len(), 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_TOO_MANY_REVISIONS")]
+    fn revisions_are_capped() {
+        let mut contract = contract_with_vetoed_proposal();
+        for budget in 0..=MAX_REVISIONS_PER_PAYOUT as u64 {
+            contract.amend_payout(PayoutKind::Proposal, 1, open_proposal_input(budget + 20));
+        }
+    }
+}
@@ -0,0 +1,107 @@
+use near_sdk::near_bindgen;
+
+use super::*;
+
+/// The appeal of a proposer against the rejection of their payout, which
+/// puts the payout under consideration again for a new voting round
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Appeal {
+    /// why the proposer disagrees with the rejection
+    pub reason: String,
+    /// the block timestamp at which the appeal was filed
+    pub filed_at: U64,
+    /// the votes of the round that rejected the payout
+    pub rejected_votes: HashMap<AccountId, vote::VoteRecord>,
+    /// the status the payout was finalized with after the appeal, none while
+    /// it is under consideration
+    pub outcome: Option<PayoutStatus>,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Appeal the rejection of a payout, which is put under consideration
+    /// again with a new voting period, no votes or vetoes, and the current
+    /// reward schedule. Can only be done once per payout by its proposer, who
+    /// attaches a new bond.
+    #[payable]
+    pub fn appeal_payout(&mut self, kind: PayoutKind, id: u64, reason: String) {
+        match kind {
+            PayoutKind::Proposal => self.internal_appeal_payout::<Proposal>(id, reason),
+            PayoutKind::Bounty => self.internal_appeal_payout::<Bounty>(id, reason),
+            PayoutKind::Miscellaneous => self.internal_appeal_payout::<Miscellaneous>(id, reason),
+            PayoutKind::Referral => self.internal_appeal_payout::<Referral>(id, reason),
+            PayoutKind::Governance => self.internal_appeal_payout::<GovernanceProposal>(id, reason),
+        }
+    }
+}
+
+impl Contract {
+    fn internal_appeal_payout<T: PayoutInfo>(&mut self, id: u64, reason: String) {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        if payout.status != PayoutStatus::Rejected {
+            panic!("{}: {}", error::ERR_NOT_PERMITTED, "payout not rejected");
+        }
+        // only the proposer can appeal
+        if payout.proposer != env::predecessor_account_id() {
+            panic!("{}", error::ERR_NOT_PERMITTED);
+        }
+        if payout.appeal.is_some() {
+            panic!("{}", error::ERR_ALREADY_APPEALED);
+        }
+        if reason.trim().is_empty() {
+            panic!("{}", error::ERR_APPEAL_REASON_REQUIRED);
+        }
+        let bond = self.internal_take_bond();
+        // the records the payout linked to may have been claimed since the
+        // rejection, so it is validated again
+        T::unlink(self, id, &payout.info);
+        T::validate(self, id, &payout.info);
+
+        payout.appeal = Some(Appeal {
+            reason,
+            filed_at: U64(env::block_timestamp()),
+            rejected_votes: std::mem::take(&mut payout.votes),
+            outcome: None,
+        });
+        payout.votes_count = VotesCount::new();
+        payout.vetoes.clear();
+        // the payout was validated again against the current reward schedule
+        payout.reward_schedule_version = self.reward_schedule_version;
+        payout.status = PayoutStatus::UnderConsideration;
+        payout.submission_time = U64(env::block_timestamp());
+        payout.council_version = self.council_version;
+        payout.bond = U128(bond);
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::payout::fixtures::{contract_with_proposal, open_proposal};
+
+    #[test]
+    fn appeal_resets_vetoes_and_uses_the_current_schedule() {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(10u128.pow(24))
+            .build());
+        let mut payout = open_proposal();
+        payout.status = PayoutStatus::Rejected;
+        payout
+            .vetoes
+            .insert(accounts(0), "not an event".to_string());
+        let mut contract = contract_with_proposal(payout);
+        contract.reward_schedule_version = 1;
+
+        contract.appeal_payout(PayoutKind::Proposal, 1, "the event happened".to_string());
+        let payout = contract.proposals.get(&1).unwrap();
+        assert_eq!(payout.status, PayoutStatus::UnderConsideration);
+        assert!(payout.vetoes.is_empty());
+        assert_eq!(payout.reward_schedule_version, 1);
+    }
+}
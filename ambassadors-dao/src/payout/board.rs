@@ -0,0 +1,295 @@
+use std::cmp::min;
+
+use near_sdk::near_bindgen;
+
+use super::{types::Action, *};
+
+/// A task published on the board that ambassadors can claim, and complete
+/// by submitting an open bounty submission before the deadline
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OpenBounty {
+    /// the account who published the bounty
+    pub publisher: AccountId,
+    /// a description of the task
+    pub task: String,
+    /// the amount paid for each approved submission
+    pub reward: USD,
+    /// the block timestamp after which no submission can be made
+    pub deadline: U64,
+    /// the maximum number of ambassadors who can claim the bounty
+    pub max_claimants: u64,
+    /// the ambassadors who claimed the bounty and the id of their last
+    /// submission, if any
+    pub claims: HashMap<AccountId, Option<u64>>,
+}
+
+impl OpenBounty {
+    /// if submissions can still be made
+    pub fn is_open(&self) -> bool {
+        env::block_timestamp() < self.deadline.0
+    }
+}
+
+/// This is format of output via JSON for the open bounty.
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct OpenBountyOutput {
+    /// Id of the open bounty.
+    pub id: u64,
+    #[serde(flatten)]
+    pub open_bounty: OpenBounty,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Publish a bounty on the board. Can only be done by the accounts the
+    /// policy allows to take the `PublishBounty` action on bounties
+    pub fn publish_open_bounty(
+        &mut self,
+        task: String,
+        reward: USD,
+        deadline: U64,
+        max_claimants: u64,
+    ) -> u64 {
+        if !self.internal_is_allowed(&PayoutKind::Bounty, &Action::PublishBounty) {
+            panic!("{}", error::ERR_NOT_PERMITTED);
+        }
+        let signer = env::signer_account_id();
+        if task.trim().is_empty()
+            || reward.0 == 0
+            || max_claimants == 0
+            || deadline.0 <= env::block_timestamp()
+        {
+            panic!("{}", error::ERR_INVALID_OPEN_BOUNTY);
+        }
+        let new_id = self.last_open_bounty_id + 1;
+        self.open_bounties.insert(
+            &new_id,
+            &OpenBounty {
+                publisher: signer,
+                task,
+                reward,
+                deadline,
+                max_claimants,
+                claims: HashMap::new(),
+            },
+        );
+        self.last_open_bounty_id = new_id;
+        new_id
+    }
+
+    /// Claim a bounty of the board before its deadline, anyone who can add
+    /// bounty payouts can claim
+    pub fn claim_open_bounty(&mut self, id: u64) {
+        self.internal_assert_can_add_payout(&PayoutKind::Bounty);
+        let mut open_bounty = self
+            .open_bounties
+            .get(&id)
+            .expect(error::ERR_OPEN_BOUNTY_NOT_FOUND);
+        if !open_bounty.is_open() {
+            panic!("{}", error::ERR_OPEN_BOUNTY_CLOSED);
+        }
+        let signer = env::signer_account_id();
+        if open_bounty.claims.contains_key(&signer) {
+            panic!("{}: {}", error::ERR_NOT_PERMITTED, "already claimed");
+        }
+        if open_bounty.claims.len() as u64 >= open_bounty.max_claimants {
+            panic!("{}", error::ERR_OPEN_BOUNTY_FULLY_CLAIMED);
+        }
+        open_bounty.claims.insert(signer, None);
+        self.open_bounties.insert(&id, &open_bounty);
+    }
+
+    /// Get specific open bounty
+    pub fn get_open_bounty(&self, id: u64) -> OpenBountyOutput {
+        let open_bounty = self
+            .open_bounties
+            .get(&id)
+            .expect(error::ERR_OPEN_BOUNTY_NOT_FOUND);
+        OpenBountyOutput { id, open_bounty }
+    }
+
+    /// Get the number of open bounties, also happens to be the ID of the latest one
+    pub fn get_last_open_bounty_id(&self) -> u64 {
+        self.last_open_bounty_id
+    }
+
+    /// Get open bounties in paginated view.
+    pub fn get_open_bounties(&self, from_index: u64, limit: u64) -> Vec<OpenBountyOutput> {
+        (from_index..=min(from_index + limit, self.last_open_bounty_id))
+            .filter_map(|id| {
+                self.open_bounties
+                    .get(&id)
+                    .map(|open_bounty| OpenBountyOutput { id, open_bounty })
+            })
+            .collect()
+    }
+}
+
+impl Contract {
+    /// Record the submission of a claimant of an open bounty. Panics if the
+    /// deadline passed, if the signer did not claim the bounty or is not the
+    /// submitter, or if their last submission was approved or is still under
+    /// consideration, a last submission whose voting period is over is expired
+    /// first.
+    pub(crate) fn internal_submit_open_bounty(
+        &mut self,
+        id: u64,
+        submission: &SubmissionInfo,
+        bounty_id: u64,
+    ) {
+        let mut open_bounty = self
+            .open_bounties
+            .get(&id)
+            .expect(error::ERR_OPEN_BOUNTY_NOT_FOUND);
+        if !open_bounty.is_open() {
+            panic!("{}", error::ERR_OPEN_BOUNTY_CLOSED);
+        }
+        let signer = env::predecessor_account_id();
+        if submission.account_id != signer {
+            panic!(
+                "{}: {}",
+                error::ERR_NOT_PERMITTED,
+                "only the claimant can submit their work"
+            );
+        }
+        let last_submission = open_bounty
+            .claims
+            .get(&signer)
+            .expect(error::ERR_OPEN_BOUNTY_NOT_CLAIMED);
+        if let Some(last_id) = *last_submission {
+            match self.internal_expire_payout::<Bounty>(last_id) {
+                PayoutStatus::Approved | PayoutStatus::UnderConsideration => {
+                    panic!("{}", error::ERR_OPEN_BOUNTY_ALREADY_SUBMITTED)
+                }
+                _ => {}
+            }
+        }
+        open_bounty.claims.insert(signer, Some(bounty_id));
+        self.open_bounties.insert(&id, &open_bounty);
+    }
+
+    /// forget the submission of a claimant of an open bounty
+    pub(crate) fn internal_withdraw_open_bounty_submission(&mut self, id: u64, bounty_id: u64) {
+        if let Some(mut open_bounty) = self.open_bounties.get(&id) {
+            for last_submission in open_bounty.claims.values_mut() {
+                if *last_submission == Some(bounty_id) {
+                    *last_submission = None;
+                }
+            }
+            self.open_bounties.insert(&id, &open_bounty);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::policy::DEFAULT_VOTING_PERIOD;
+
+    const DEADLINE: u64 = 2 * DEFAULT_VOTING_PERIOD;
+
+    fn signed_by(account: usize, timestamp: u64) {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(account))
+            .predecessor_account_id(accounts(account))
+            .attached_deposit(10u128.pow(24))
+            .block_timestamp(timestamp)
+            .build());
+    }
+
+    /// a contract with accounts(0), accounts(1) and accounts(2) on the council
+    /// and a bounty published by accounts(0) at id 1
+    fn contract_with_open_bounty(max_claimants: u64) -> Contract {
+        signed_by(0, 0);
+        let mut contract = Contract::new(
+            "dao".to_string(),
+            "testing".to_string(),
+            vec![accounts(0), accounts(1), accounts(2)],
+            None,
+        );
+        contract.publish_open_bounty(
+            "write a tutorial".to_string(),
+            USD::from_dollars(20),
+            U64(DEADLINE),
+            max_claimants,
+        );
+        contract
+    }
+
+    fn submit(contract: &mut Contract, account: usize) -> u64 {
+        contract.add_payout_bounty(PayoutInput {
+            description: "tutorial".to_string(),
+            information: Bounty::OpenBountySubmission {
+                open_bounty_id: 1,
+                submission: SubmissionInfo {
+                    name: "tutorial".to_string(),
+                    account_id: accounts(account),
+                    submission_link: "https://example.com/tutorial".to_string(),
+                },
+            },
+        })
+    }
+
+    #[test]
+    fn claim_is_recorded() {
+        let mut contract = contract_with_open_bounty(2);
+        signed_by(1, 0);
+        contract.claim_open_bounty(1);
+        let open_bounty = contract.get_open_bounty(1).open_bounty;
+        assert_eq!(open_bounty.claims.get(&accounts(1)), Some(&None));
+        let id = submit(&mut contract, 1);
+        let open_bounty = contract.get_open_bounty(1).open_bounty;
+        assert_eq!(open_bounty.claims.get(&accounts(1)), Some(&Some(id)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OPEN_BOUNTY_FULLY_CLAIMED")]
+    fn claimants_are_capped() {
+        let mut contract = contract_with_open_bounty(1);
+        signed_by(1, 0);
+        contract.claim_open_bounty(1);
+        signed_by(2, 0);
+        contract.claim_open_bounty(1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OPEN_BOUNTY_CLOSED")]
+    fn submission_after_the_deadline_is_rejected() {
+        let mut contract = contract_with_open_bounty(1);
+        signed_by(1, 0);
+        contract.claim_open_bounty(1);
+        signed_by(1, DEADLINE);
+        submit(&mut contract, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_OPEN_BOUNTY_ALREADY_SUBMITTED")]
+    fn pending_submission_cannot_be_replaced() {
+        let mut contract = contract_with_open_bounty(1);
+        signed_by(1, 0);
+        contract.claim_open_bounty(1);
+        submit(&mut contract, 1);
+        submit(&mut contract, 1);
+    }
+
+    #[test]
+    fn expired_submission_can_be_replaced() {
+        let mut contract = contract_with_open_bounty(1);
+        signed_by(1, 0);
+        contract.claim_open_bounty(1);
+        let first = submit(&mut contract, 1);
+        signed_by(1, DEFAULT_VOTING_PERIOD);
+        let second = submit(&mut contract, 1);
+        assert_eq!(
+            contract.bounties.get(&first).unwrap().status,
+            PayoutStatus::Expired
+        );
+        let open_bounty = contract.get_open_bounty(1).open_bounty;
+        assert_eq!(open_bounty.claims.get(&accounts(1)), Some(&Some(second)));
+    }
+}
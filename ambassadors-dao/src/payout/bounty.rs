@@ -1,9 +1,29 @@
-use near_sdk::{env, near_bindgen};
+use near_sdk::near_bindgen;
 
 use super::{types::Action, *};
+use crate::amounts::{Prize, RewardSchedule};
 
 pub type BountyPayout = Payout<Bounty>;
 
+/// The completion bounty claiming an approved hackathon or meme contest
+/// proposal, and the reconciliation of its payments with the part of the
+/// proposal budget held in escrow for it
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct CompletionClaim {
+    /// the completion bounty
+    pub bounty_id: u64,
+    /// the completion amount and the prizes held in escrow when the proposal
+    /// was approved
+    pub escrow: USD,
+    /// the amount the completion bounty scheduled to pay when it was approved,
+    /// none until it is approved. The state of the transfers is in the
+    /// payments of the bounty.
+    pub scheduled: Option<USD>,
+    /// the part of the escrow that was not scheduled, none until it is approved
+    pub unspent: Option<USD>,
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
 #[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
 #[serde(crate = "near_sdk::serde")]
@@ -14,8 +34,12 @@ pub enum Bounty {
         /// number of submissions
         num_of_submissions: u64,
         /// information of the winners
-        /// order of the winners w.r.to their ranks
+        /// order of the winners w.r.to the prize table
         winners_info: Vec<SubmissionInfo>,
+        /// the approved proposal of the event, whose prize table is used if it
+        /// has one, the prizes of the reward schedule are used otherwise,
+        /// required for new completions
+        proposal_id: Option<u64>,
     },
     MemeContestCompletion {
         /// number of registrations
@@ -23,8 +47,12 @@ pub enum Bounty {
         /// number of submissions
         num_of_submissions: u64,
         /// information of the winners
-        /// order of the winners w.r.to their ranks
+        /// order of the winners w.r.to the prize table
         winners_info: Vec<SubmissionInfo>,
+        /// the approved proposal of the event, whose prize table is used if it
+        /// has one, the prizes of the reward schedule are used otherwise,
+        /// required for new completions
+        proposal_id: Option<u64>,
     },
     Webinar {
         /// number of registrations
@@ -42,50 +70,76 @@ pub enum Bounty {
         /// a list of the name of tools used
         tools_used: Vec<String>,
     },
+    /// the work of a claimant of a bounty of the board, paid the reward of
+    /// the bounty once approved
+    OpenBountySubmission {
+        /// the bounty of the board
+        open_bounty_id: u64,
+        /// the work submitted by the claimant
+        submission: SubmissionInfo,
+    },
 }
 
-impl From<PayoutInput<Bounty>> for Payout<Bounty> {
-    fn from(input: PayoutInput<Bounty>) -> Self {
-        Self {
-            proposer: env::predecessor_account_id(),
-            description: input.description,
-            info: input.information,
-            status: PayoutStatus::UnderConsideration,
-            votes_count: VotesCount::new(),
-            votes: HashMap::default(),
-        }
+impl PayoutInfo for Bounty {
+    const KIND: PayoutKind = PayoutKind::Bounty;
+    const ERR_NOT_FOUND: &'static str = error::ERR_BOUNTY_NOT_FOUND;
+
+    fn payouts(contract: &Contract) -> &LookupMap<u64, Payout<Self>> {
+        &contract.bounties
     }
-}
 
-#[near_bindgen]
-impl Contract {
-    /// create a bounty payout
-    pub fn add_payout_bounty(&mut self, payout: PayoutInput<Bounty>) -> u64 {
-        // validate input
-        match &payout.information {
+    fn payouts_mut(contract: &mut Contract) -> &mut LookupMap<u64, Payout<Self>> {
+        &mut contract.bounties
+    }
+
+    fn last_id(contract: &Contract) -> u64 {
+        contract.last_bounty_id
+    }
+
+    fn validate(contract: &mut Contract, id: u64, info: &Self) {
+        match info {
             Bounty::HackathonCompletion {
                 num_of_registrations,
                 winners_info,
+                proposal_id,
                 ..
-            } => {
-                if *num_of_registrations < 20 {
-                    panic!("ERR_MIN_SUBMISSION_LIMIT_NOT_SATISFIED")
-                }
-                if winners_info.len() != 3 {
-                    panic!("ERR_WINNERS_INFO_MUST_HAVE_THREE_ENTRIES")
-                }
             }
-            Bounty::MemeContestCompletion {
+            | Bounty::MemeContestCompletion {
                 num_of_registrations,
                 winners_info,
+                proposal_id,
                 ..
             } => {
                 if *num_of_registrations < 20 {
                     panic!("ERR_MIN_SUBMISSION_LIMIT_NOT_SATISFIED")
                 }
-                if winners_info.len() != 3 {
-                    panic!("ERR_WINNERS_INFO_MUST_HAVE_THREE_ENTRIES")
+                let proposal_id = proposal_id.expect(error::ERR_PROPOSAL_ID_REQUIRED);
+                // every prize must have a winner
+                let amounts = contract.internal_reward_schedule(contract.reward_schedule_version);
+                let prizes = contract.internal_prize_table(info, &amounts);
+                if winners_info.len() != prizes.len() {
+                    panic!("{}", error::ERR_WINNERS_DO_NOT_MATCH_PRIZES)
+                }
+                let total = prizes
+                    .iter()
+                    .try_fold(completion_amount(info, &amounts), |total, prize| {
+                        total.checked_add(prize.amount)
+                    })
+                    .expect(error::ERR_AMOUNT_OVERFLOW);
+                let escrow = contract.internal_assert_can_claim_proposal(proposal_id);
+                if total > escrow {
+                    panic!("{}", error::ERR_COMPLETION_EXCEEDS_BUDGET)
                 }
+                // the proposal is claimed until the bounty is rejected or expires
+                contract.completion_claims.insert(
+                    &proposal_id,
+                    &CompletionClaim {
+                        bounty_id: id,
+                        escrow,
+                        scheduled: None,
+                        unspent: None,
+                    },
+                );
             }
             Bounty::Webinar {
                 num_of_attendees,
@@ -112,92 +166,392 @@ impl Contract {
                     panic!("ERR_TOOLS_USED_CANNOT_BE_EMPTY")
                 }
             }
+            Bounty::OpenBountySubmission {
+                open_bounty_id,
+                submission,
+            } => {
+                validation::assert_valid_resource_url(&submission.submission_link);
+                contract.internal_submit_open_bounty(*open_bounty_id, submission, id);
+            }
+        }
+    }
+
+    fn unlink(contract: &mut Contract, id: u64, info: &Self) {
+        match info {
+            Bounty::HackathonCompletion {
+                proposal_id: Some(proposal_id),
+                ..
+            }
+            | Bounty::MemeContestCompletion {
+                proposal_id: Some(proposal_id),
+                ..
+            } => {
+                let is_claimant = contract
+                    .completion_claims
+                    .get(proposal_id)
+                    .is_some_and(|claim| claim.bounty_id == id);
+                if is_claimant {
+                    contract.completion_claims.remove(proposal_id);
+                }
+            }
+            Bounty::OpenBountySubmission { open_bounty_id, .. } => {
+                contract.internal_withdraw_open_bounty_submission(*open_bounty_id, id);
+            }
+            _ => {}
+        }
+    }
+
+    fn approved_transfers(
+        contract: &Contract,
+        id: u64,
+        payout: &Payout<Self>,
+    ) -> Result<Vec<(AccountId, USD)>, String> {
+        let amounts = contract.internal_reward_schedule(payout.reward_schedule_version);
+        // send the respective winners tokens
+        let transfers = match &payout.info {
+            Bounty::HackathonCompletion {
+                winners_info,
+                proposal_id,
+                ..
+            }
+            | Bounty::MemeContestCompletion {
+                winners_info,
+                proposal_id,
+                ..
+            } => {
+                let prizes = contract.internal_prize_table(&payout.info, &amounts);
+                let transfers: Vec<(AccountId, USD)> = std::iter::once((
+                    payout.proposer.clone(),
+                    completion_amount(&payout.info, &amounts),
+                ))
+                .chain(winner_transfers(winners_info, &prizes))
+                .collect();
+                // a completion is paid out of the escrow of the proposal it claims
+                if let Some(proposal_id) = proposal_id {
+                    let claim = contract
+                        .completion_claims
+                        .get(proposal_id)
+                        .filter(|claim| claim.bounty_id == id)
+                        .ok_or("the proposal is claimed by another completion")?;
+                    if total_amount(&transfers) > claim.escrow {
+                        return Err("the completion exceeds the escrow of the proposal".into());
+                    }
+                }
+                transfers
+            }
+            Bounty::Webinar { .. } => {
+                vec![(payout.proposer.clone(), amounts.webinar_completion)]
+            }
+            Bounty::ContentCoordination { .. } => {
+                vec![(payout.proposer.clone(), amounts.content_coordination)]
+            }
+            Bounty::OpenBountySubmission { open_bounty_id, .. } => {
+                let open_bounty = contract
+                    .open_bounties
+                    .get(open_bounty_id)
+                    .expect(error::ERR_OPEN_BOUNTY_NOT_FOUND);
+                vec![(payout.proposer.clone(), open_bounty.reward)]
+            }
         };
-        // anyone can create this, no permission checks needed
+        Ok(transfers)
+    }
 
+    fn on_approved(
+        contract: &mut Contract,
+        id: u64,
+        payout: &Payout<Self>,
+        transfers: &[(AccountId, USD)],
+    ) {
+        if let Bounty::HackathonCompletion {
+            proposal_id: Some(proposal_id),
+            ..
+        }
+        | Bounty::MemeContestCompletion {
+            proposal_id: Some(proposal_id),
+            ..
+        } = &payout.info
+        {
+            contract.internal_reconcile_completion(*proposal_id, id, transfers);
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// create a bounty payout
+    #[payable]
+    pub fn add_payout_bounty(&mut self, payout: PayoutInput<Bounty>) -> u64 {
+        self.internal_assert_can_add_payout(&PayoutKind::Bounty);
+        let bond = self.internal_take_bond();
+        // validate input
+        Bounty::validate(self, self.last_bounty_id + 1, &payout.information);
         // add the bounty to Contract.bountys
         let new_id = self.last_bounty_id + 1;
-        self.bounties.insert(&new_id, &Payout::from(payout));
+        self.bounties.insert(
+            &new_id,
+            &Payout::new(
+                payout,
+                self.reward_schedule_version,
+                self.council_version,
+                bond,
+            ),
+        );
         self.last_bounty_id = new_id;
         new_id
     }
 
     /// act on a bounty payout
     pub fn act_payout_bounty(&mut self, id: u64, action: Action, note: Option<String>) {
-        let mut bounty = match self.bounties.get(&id) {
-            Some(b) => b,
-            None => {
-                panic!("{}", error::ERR_BOUNTY_NOT_FOUND);
+        self.internal_act_on_payout::<Bounty>(id, action, note);
+    }
+
+    /// Returns the completion bounty claiming a proposal and its reconciliation
+    /// with the budget of the proposal, if any.
+    pub fn get_proposal_completion(&self, proposal_id: u64) -> Option<CompletionClaim> {
+        self.completion_claims.get(&proposal_id)
+    }
+}
+
+/// the amount paid to the organizer of a hackathon or meme contest
+fn completion_amount(bounty: &Bounty, amounts: &RewardSchedule) -> USD {
+    match bounty {
+        Bounty::HackathonCompletion { .. } => amounts.hackathon_completion,
+        Bounty::MemeContestCompletion { .. } => amounts.meme_contest_completion,
+        _ => USD(0),
+    }
+}
+
+/// the sum of the amounts of the transfers
+fn total_amount(transfers: &[(AccountId, USD)]) -> USD {
+    transfers
+        .iter()
+        .try_fold(USD(0), |total, (_, amount)| total.checked_add(*amount))
+        .expect(error::ERR_AMOUNT_OVERFLOW)
+}
+
+/// pair each winner with the prize of the same position in the prize table,
+/// there must be exactly one winner per prize
+fn winner_transfers<'a>(
+    winners_info: &'a [SubmissionInfo],
+    prizes: &'a [Prize],
+) -> impl Iterator<Item = (AccountId, USD)> + 'a {
+    if winners_info.len() != prizes.len() {
+        panic!("{}", error::ERR_WINNERS_DO_NOT_MATCH_PRIZES)
+    }
+    winners_info
+        .iter()
+        .zip(prizes.iter())
+        .map(|(winner, prize)| (winner.account_id.clone(), prize.amount))
+}
+
+impl Contract {
+    /// The prize table of a hackathon or meme contest completion, taken from
+    /// its proposal if it has one, or from the given reward schedule.
+    fn internal_prize_table(&self, bounty: &Bounty, amounts: &RewardSchedule) -> Vec<Prize> {
+        let (proposal_id, default_prizes) = match bounty {
+            Bounty::HackathonCompletion { proposal_id, .. } => {
+                (proposal_id, &amounts.hackathon_prizes)
+            }
+            Bounty::MemeContestCompletion { proposal_id, .. } => {
+                (proposal_id, &amounts.meme_contest_prizes)
             }
+            _ => return Vec::new(),
         };
-        internal_act_payout(
-            self.members.is_council_member(&env::signer_account_id()),
-            self.members.get_council_size() as u64,
-            &mut bounty,
-            action,
-            note,
-        );
-        self.bounties.insert(&id, &bounty);
-        // check if payout state is approved
-        if bounty.status == PayoutStatus::Approved {
-            // send the respective winners tokens
-            // here tokens is in yoctonear
-            let transfers = match bounty.info {
-                Bounty::HackathonCompletion { winners_info, .. } => {
-                    vec![
-                        (
-                            bounty.proposer.clone(),
-                            amounts::HACKATHON_COMPLETION_AMOUNT,
-                        ),
-                        (
-                            winners_info[0].account_id.clone(),
-                            amounts::HACKATHON_FIRST_PLACE_AMOUNT,
-                        ),
-                        (
-                            winners_info[1].account_id.clone(),
-                            amounts::HACKATHON_SECOND_PLACE_AMOUNT,
-                        ),
-                        (
-                            winners_info[2].account_id.clone(),
-                            amounts::HACKATHON_THIRD_PLACE_AMOUNT,
-                        ),
-                    ]
-                }
-                Bounty::MemeContestCompletion { winners_info, .. } => {
-                    vec![
-                        (
-                            bounty.proposer.clone(),
-                            amounts::MEME_CONTEST_COMPLETION_AMOUNT,
-                        ),
-                        (
-                            winners_info[0].account_id.clone(),
-                            amounts::MEME_CONTEST_FIRST_PLACE_AMOUNT,
-                        ),
-                        (
-                            winners_info[1].account_id.clone(),
-                            amounts::MEME_CONTEST_SECOND_PLACE_AMOUNT,
-                        ),
-                        (
-                            winners_info[2].account_id.clone(),
-                            amounts::MEME_CONTEST_THIRD_PLACE_AMOUNT,
-                        ),
-                    ]
-                }
-                Bounty::Webinar { .. } => {
-                    vec![(bounty.proposer, amounts::WEBINAR_COMPLETION_AMOUNT)]
-                }
-                Bounty::ContentCoordination { .. } => {
-                    vec![(bounty.proposer, amounts::CONTENT_COORDINATION_AMOUNT)]
+        let proposal_id = match proposal_id {
+            Some(id) => id,
+            None => return default_prizes.clone(),
+        };
+        let proposal = self
+            .proposals
+            .get(proposal_id)
+            .expect(error::ERR_PROPOSAL_NOT_FOUND);
+        match (bounty, proposal.info) {
+            (Bounty::HackathonCompletion { .. }, Proposal::Hackathon { prize_table, .. })
+            | (Bounty::MemeContestCompletion { .. }, Proposal::MemeContest { prize_table, .. }) => {
+                prize_table.unwrap_or_else(|| default_prizes.clone())
+            }
+            _ => panic!("{}", error::ERR_PROPOSAL_KIND_MISMATCH),
+        }
+    }
+
+    /// Check the proposal is approved, was submitted by the signer and is not
+    /// claimed by another completion bounty, the claim of a bounty whose
+    /// voting period is over is released once the bounty is expired. Returns
+    /// the part of the budget of the proposal held in escrow for its completion.
+    fn internal_assert_can_claim_proposal(&mut self, proposal_id: u64) -> USD {
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(error::ERR_PROPOSAL_NOT_FOUND);
+        if proposal.status != PayoutStatus::Approved {
+            panic!("{}", error::ERR_PROPOSAL_NOT_APPROVED)
+        }
+        if proposal.proposer != env::predecessor_account_id() {
+            panic!(
+                "{}: {}",
+                error::ERR_NOT_PERMITTED,
+                "only the organizer of the proposal can claim its completion"
+            )
+        }
+        if !matches!(
+            proposal.info,
+            Proposal::Hackathon { .. } | Proposal::MemeContest { .. }
+        ) {
+            panic!("{}", error::ERR_PROPOSAL_KIND_MISMATCH)
+        }
+        if let Some(claim) = self.completion_claims.get(&proposal_id) {
+            match self.internal_expire_payout::<Bounty>(claim.bounty_id) {
+                PayoutStatus::Approved | PayoutStatus::UnderConsideration => {
+                    panic!("{}", error::ERR_PROPOSAL_ALREADY_CLAIMED)
                 }
-            };
-            let num_of_transfers = transfers.len();
-            // get the exchange rate
-            self.get_exchange_rate().then(ext::make_transfers(
-                transfers,
-                env::current_account_id(),
-                0,
-                Gas(types::ONE_TGAS * num_of_transfers as u64),
-            ));
+                _ => {}
+            }
         }
+        self.internal_completion_escrow(&proposal)
+    }
+
+    /// The part of the budget of a hackathon or meme contest proposal held in
+    /// escrow for its completion bounty when it is approved: the completion
+    /// amount and the prizes of the reward schedule of the proposal, at most
+    /// the budget. Nothing is held for the other proposals.
+    pub(crate) fn internal_completion_escrow(&self, proposal: &ProposalPayout) -> USD {
+        let amounts = self.internal_reward_schedule(proposal.reward_schedule_version);
+        let (estimated_budget, completion, prizes) = match &proposal.info {
+            Proposal::Hackathon {
+                estimated_budget,
+                prize_table,
+                ..
+            } => (
+                estimated_budget,
+                amounts.hackathon_completion,
+                prize_table.as_ref().unwrap_or(&amounts.hackathon_prizes),
+            ),
+            Proposal::MemeContest {
+                estimated_budget,
+                prize_table,
+                ..
+            } => (
+                estimated_budget,
+                amounts.meme_contest_completion,
+                prize_table.as_ref().unwrap_or(&amounts.meme_contest_prizes),
+            ),
+            Proposal::Open { .. } | Proposal::MilestoneReport { .. } => return USD(0),
+        };
+        let total = prizes.iter().fold(completion.0, |total, prize| {
+            total.saturating_add(prize.amount.0)
+        });
+        USD(total.min(estimated_budget.0))
+    }
+
+    /// record the amount scheduled by an approved completion bounty against the
+    /// escrow of the proposal it claims
+    fn internal_reconcile_completion(
+        &mut self,
+        proposal_id: u64,
+        bounty_id: u64,
+        transfers: &[(AccountId, USD)],
+    ) {
+        let mut claim = match self.completion_claims.get(&proposal_id) {
+            Some(claim) if claim.bounty_id == bounty_id => claim,
+            _ => return,
+        };
+        let scheduled = total_amount(transfers);
+        claim.scheduled = Some(scheduled);
+        claim.unspent = Some(USD(claim.escrow.0.saturating_sub(scheduled.0)));
+        self.completion_claims.insert(&proposal_id, &claim);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::payout::fixtures::contract_with_proposal;
+    use crate::policy::DEFAULT_VOTING_PERIOD;
+
+    fn signed_at(timestamp: u64) {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .attached_deposit(10u128.pow(24))
+            .block_timestamp(timestamp)
+            .build());
+    }
+
+    /// a contract with an approved $100 hackathon of accounts(0) at id 1,
+    /// with a single prize of $50
+    fn contract_with_hackathon() -> Contract {
+        signed_at(0);
+        let mut hackathon = Payout::new(
+            PayoutInput {
+                description: "hackathon".to_string(),
+                information: Proposal::Hackathon {
+                    expected_registrations: 100,
+                    estimated_budget: USD::from_dollars(100),
+                    supporting_document: "https://example.com/doc.pdf".to_string(),
+                    prize_table: Some(vec![Prize {
+                        category: None,
+                        rank: 1,
+                        amount: USD::from_dollars(50),
+                    }]),
+                    milestones: None,
+                },
+            },
+            0,
+            0,
+            0,
+        );
+        hackathon.status = PayoutStatus::Approved;
+        contract_with_proposal(hackathon)
+    }
+
+    fn submit_completion(contract: &mut Contract) -> u64 {
+        contract.add_payout_bounty(PayoutInput {
+            description: "completion".to_string(),
+            information: Bounty::HackathonCompletion {
+                num_of_registrations: 100,
+                num_of_submissions: 10,
+                winners_info: vec![SubmissionInfo {
+                    name: "winner".to_string(),
+                    account_id: accounts(2),
+                    submission_link: "https://example.com/project".to_string(),
+                }],
+                proposal_id: Some(1),
+            },
+        })
+    }
+
+    #[test]
+    fn hackathon_approval_holds_the_prizes_in_escrow() {
+        let contract = contract_with_hackathon();
+        let proposal = contract.proposals.get(&1).unwrap();
+        // the completion amount of $40 and the prize of $50 are held back
+        let transfers = Proposal::approved_transfers(&contract, 1, &proposal).unwrap();
+        assert_eq!(transfers, vec![(accounts(0), USD::from_dollars(10))]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_PROPOSAL_ALREADY_CLAIMED")]
+    fn proposal_cannot_be_claimed_twice() {
+        let mut contract = contract_with_hackathon();
+        submit_completion(&mut contract);
+        submit_completion(&mut contract);
+    }
+
+    #[test]
+    fn claim_of_an_expired_completion_is_released() {
+        let mut contract = contract_with_hackathon();
+        let first = submit_completion(&mut contract);
+        signed_at(DEFAULT_VOTING_PERIOD);
+        let second = submit_completion(&mut contract);
+        assert_eq!(
+            contract.bounties.get(&first).unwrap().status,
+            PayoutStatus::Expired
+        );
+        let claim = contract.get_proposal_completion(1).unwrap();
+        assert_eq!(claim.bounty_id, second);
+        assert_eq!(claim.escrow, USD::from_dollars(90));
     }
 }
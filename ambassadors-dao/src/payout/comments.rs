@@ -0,0 +1,148 @@
+use near_sdk::near_bindgen;
+
+use super::*;
+
+/// The maximum number of comments in the thread of a payout
+pub const MAX_COMMENTS_PER_PAYOUT: usize = 100;
+/// The maximum length in bytes of a comment, votes included
+pub const MAX_COMMENT_LENGTH: usize = 1000;
+
+/// A comment in the thread of a payout
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Comment {
+    /// the proposer or council member who posted the comment
+    pub author: AccountId,
+    pub text: String,
+    /// the block timestamp at which the comment was posted
+    pub posted_at: U64,
+}
+
+/// check a comment is not blank and not longer than the maximum length
+pub fn assert_valid_comment(text: &str) {
+    if text.trim().is_empty() || text.len() > MAX_COMMENT_LENGTH {
+        panic!("{}", error::ERR_INVALID_COMMENT);
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Post a comment in the thread of a payout under consideration. Can only
+    /// be done by the proposer of the payout or a council member, who attach
+    /// a deposit covering the storage of the comment. The excess is refunded.
+    #[payable]
+    pub fn add_payout_comment(&mut self, kind: PayoutKind, id: u64, text: String) {
+        let (proposer, is_pending) = match kind {
+            PayoutKind::Proposal => self.internal_comment_target::<Proposal>(id),
+            PayoutKind::Bounty => self.internal_comment_target::<Bounty>(id),
+            PayoutKind::Miscellaneous => self.internal_comment_target::<Miscellaneous>(id),
+            PayoutKind::Referral => self.internal_comment_target::<Referral>(id),
+            PayoutKind::Governance => self.internal_comment_target::<GovernanceProposal>(id),
+        };
+        let signer = env::signer_account_id();
+        if signer != proposer && !self.members.is_council_member(&signer) {
+            panic!("{}", error::ERR_NOT_PERMITTED);
+        }
+        if !is_pending {
+            panic!(
+                "{}: {}",
+                error::ERR_NOT_PERMITTED,
+                "payout not under consideration"
+            );
+        }
+        assert_valid_comment(&text);
+        let key = (kind, id);
+        let mut comments = self.comments.get(&key).unwrap_or_default();
+        if comments.len() >= MAX_COMMENTS_PER_PAYOUT {
+            panic!("{}", error::ERR_COMMENT_THREAD_FULL);
+        }
+        let storage_before = env::storage_usage();
+        comments.push(Comment {
+            author: signer,
+            text,
+            posted_at: U64(env::block_timestamp()),
+        });
+        self.comments.insert(&key, &comments);
+        let storage_cost = (env::storage_usage().saturating_sub(storage_before) as Balance)
+            * env::storage_byte_cost();
+        let deposit = env::attached_deposit();
+        if deposit < storage_cost {
+            panic!("{}: {}", error::ERR_NOT_ENOUGH_DEPOSIT, storage_cost);
+        }
+        if deposit > storage_cost {
+            Promise::new(env::predecessor_account_id()).transfer(deposit - storage_cost);
+        }
+    }
+
+    /// Get the comments of a payout in paginated view, oldest first.
+    pub fn get_payout_comments(
+        &self,
+        kind: PayoutKind,
+        id: u64,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<Comment> {
+        self.comments
+            .get(&(kind, id))
+            .unwrap_or_default()
+            .into_iter()
+            .skip(from_index as usize)
+            .take(limit as usize)
+            .collect()
+    }
+}
+
+impl Contract {
+    /// the proposer of a payout and if it is still under consideration
+    fn internal_comment_target<T: PayoutInfo>(&self, id: u64) -> (AccountId, bool) {
+        let payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        let is_pending = payout.is_pending(self.vote_policy.rule_for(&T::KIND));
+        (payout.proposer, is_pending)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::payout::fixtures::{contract_with_proposal, open_proposal};
+
+    /// a contract with a proposal of accounts(1) in the given status, and a
+    /// context in which accounts(1) attaches the given deposit
+    fn contract_with_proposal_in(status: PayoutStatus, deposit: Balance) -> Contract {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(1))
+            .predecessor_account_id(accounts(1))
+            .attached_deposit(deposit)
+            .build());
+        let mut payout = open_proposal();
+        payout.status = status;
+        contract_with_proposal(payout)
+    }
+
+    #[test]
+    fn comment_is_stored() {
+        let mut contract =
+            contract_with_proposal_in(PayoutStatus::UnderConsideration, 10u128.pow(24));
+        contract.add_payout_comment(PayoutKind::Proposal, 1, "venue booked".to_string());
+        let comments = contract.get_payout_comments(PayoutKind::Proposal, 1, 0, 10);
+        assert_eq!(comments.len(), 1);
+        assert_eq!(comments[0].author, accounts(1));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_NOT_ENOUGH_DEPOSIT")]
+    fn comment_storage_must_be_paid() {
+        let mut contract = contract_with_proposal_in(PayoutStatus::UnderConsideration, 0);
+        contract.add_payout_comment(PayoutKind::Proposal, 1, "venue booked".to_string());
+    }
+
+    #[test]
+    #[should_panic(expected = "payout not under consideration")]
+    fn finalized_payout_cannot_be_commented() {
+        let mut contract = contract_with_proposal_in(PayoutStatus::Approved, 10u128.pow(24));
+        contract.add_payout_comment(PayoutKind::Proposal, 1, "venue booked".to_string());
+    }
+}
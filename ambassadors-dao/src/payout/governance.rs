@@ -0,0 +1,312 @@
+use near_sdk::near_bindgen;
+
+use super::{types::Action, *};
+use crate::amounts::RewardSchedule;
+use crate::asset::PayoutAssets;
+use crate::budget::Budgets;
+use crate::oracle::OracleConfig;
+use crate::policy::{Policy, VotePolicy};
+use crate::types::Config;
+
+pub type GovernancePayout = Payout<GovernanceProposal>;
+
+/// A change to the DAO itself, applied once the council approves it
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize)]
+#[cfg_attr(not(target_arch = "wasm32"), derive(Clone, Debug))]
+#[serde(crate = "near_sdk::serde")]
+pub enum GovernanceProposal {
+    AddCouncilMember {
+        /// the account to add to the council
+        account_id: AccountId,
+    },
+    RemoveCouncilMember {
+        /// the account to remove from the council
+        account_id: AccountId,
+    },
+    ChangeConfig {
+        /// the new name and purpose of the DAO
+        config: Config,
+    },
+    ChangePolicy {
+        /// the new voting policy
+        policy: VotePolicy,
+    },
+    ChangeRoles {
+        /// the new roles and their permissions
+        policy: Policy,
+    },
+    ChangeOracle {
+        /// the new oracle configuration
+        oracle: OracleConfig,
+    },
+    ChangePayoutAssets {
+        /// the new assets payouts are paid in
+        assets: PayoutAssets,
+    },
+    ChangeRewardSchedule {
+        /// the new amounts, applied to payouts submitted after the change
+        schedule: RewardSchedule,
+    },
+    ChangeBudgets {
+        /// the new budget caps, the amounts approved in the current periods are kept
+        budgets: Budgets,
+    },
+}
+
+impl PayoutInfo for GovernanceProposal {
+    const KIND: PayoutKind = PayoutKind::Governance;
+    const ERR_NOT_FOUND: &'static str = error::ERR_GOVERNANCE_PROPOSAL_NOT_FOUND;
+
+    fn payouts(contract: &Contract) -> &LookupMap<u64, Payout<Self>> {
+        &contract.governance_proposals
+    }
+
+    fn payouts_mut(contract: &mut Contract) -> &mut LookupMap<u64, Payout<Self>> {
+        &mut contract.governance_proposals
+    }
+
+    fn last_id(contract: &Contract) -> u64 {
+        contract.last_governance_proposal_id
+    }
+
+    fn validate(contract: &mut Contract, _id: u64, info: &Self) {
+        match info {
+            GovernanceProposal::AddCouncilMember { account_id } => {
+                if contract.members.is_council_member(account_id) {
+                    panic!("{}", error::ERR_ALREADY_COUNCIL_MEMBER);
+                }
+            }
+            GovernanceProposal::RemoveCouncilMember { account_id } => {
+                if !contract.members.is_council_member(account_id) {
+                    panic!("{}", error::ERR_COUNCIL_MEMBER_NOT_FOUND);
+                }
+                if contract.members.get_council_size() == 1 {
+                    panic!("{}", error::ERR_COUNCIL_EMPTY);
+                }
+            }
+            GovernanceProposal::ChangeConfig { config } => {
+                config.assert_valid();
+            }
+            GovernanceProposal::ChangePolicy { policy } => {
+                policy.assert_valid();
+            }
+            GovernanceProposal::ChangeRoles { policy } => {
+                policy.assert_valid();
+            }
+            GovernanceProposal::ChangeOracle { oracle } => {
+                oracle.assert_valid();
+            }
+            GovernanceProposal::ChangePayoutAssets { assets } => {
+                assets.assert_valid();
+            }
+            GovernanceProposal::ChangeRewardSchedule { schedule } => {
+                schedule.assert_valid();
+            }
+            GovernanceProposal::ChangeBudgets { budgets } => {
+                budgets.assert_valid();
+            }
+        }
+    }
+
+    fn unlink(_contract: &mut Contract, _id: u64, _info: &Self) {}
+
+    fn approved_transfers(
+        contract: &Contract,
+        _id: u64,
+        payout: &Payout<Self>,
+    ) -> Result<Vec<(AccountId, USD)>, String> {
+        // another change of the council may have been approved since the submission
+        match &payout.info {
+            GovernanceProposal::AddCouncilMember { account_id }
+                if contract.members.is_council_member(account_id) =>
+            {
+                Err(error::ERR_ALREADY_COUNCIL_MEMBER.to_string())
+            }
+            GovernanceProposal::RemoveCouncilMember { account_id }
+                if !contract.members.is_council_member(account_id) =>
+            {
+                Err(error::ERR_COUNCIL_MEMBER_NOT_FOUND.to_string())
+            }
+            GovernanceProposal::RemoveCouncilMember { .. }
+                if contract.members.get_council_size() == 1 =>
+            {
+                Err(error::ERR_COUNCIL_EMPTY.to_string())
+            }
+            // nothing is transferred
+            _ => Ok(vec![]),
+        }
+    }
+
+    fn on_approved(
+        contract: &mut Contract,
+        _id: u64,
+        payout: &Payout<Self>,
+        _transfers: &[(AccountId, USD)],
+    ) {
+        // apply the change
+        match &payout.info {
+            GovernanceProposal::AddCouncilMember { account_id } => {
+                contract.members.council.insert(account_id.clone());
+                contract.council_version += 1;
+            }
+            GovernanceProposal::RemoveCouncilMember { account_id } => {
+                contract.members.council.remove(account_id);
+                contract.council_version += 1;
+            }
+            GovernanceProposal::ChangeConfig { config } => {
+                contract.config = config.clone();
+            }
+            GovernanceProposal::ChangePolicy { policy } => {
+                contract.vote_policy = policy.clone();
+            }
+            GovernanceProposal::ChangeRoles { policy } => {
+                contract.policy = policy.clone();
+            }
+            GovernanceProposal::ChangeOracle { oracle } => {
+                contract.oracle = oracle.clone();
+            }
+            GovernanceProposal::ChangePayoutAssets { assets } => {
+                contract.payout_assets = assets.clone();
+            }
+            GovernanceProposal::ChangeRewardSchedule { schedule } => {
+                contract.internal_set_reward_schedule(schedule);
+            }
+            GovernanceProposal::ChangeBudgets { budgets } => {
+                contract.budgets = budgets.clone();
+            }
+        };
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// create a governance proposal
+    #[payable]
+    pub fn add_payout_governance(&mut self, payout: PayoutInput<GovernanceProposal>) -> u64 {
+        self.internal_assert_can_add_payout(&PayoutKind::Governance);
+        let bond = self.internal_take_bond();
+        // validate input
+        GovernanceProposal::validate(
+            self,
+            self.last_governance_proposal_id + 1,
+            &payout.information,
+        );
+
+        // add the proposal to Contract.governance_proposals
+        let new_id = self.last_governance_proposal_id + 1;
+        self.governance_proposals.insert(
+            &new_id,
+            &Payout::new(
+                payout,
+                self.reward_schedule_version,
+                self.council_version,
+                bond,
+            ),
+        );
+        self.last_governance_proposal_id = new_id;
+        new_id
+    }
+
+    /// act on a governance proposal
+    pub fn act_payout_governance(&mut self, id: u64, action: Action, note: Option<String>) {
+        self.internal_act_on_payout::<GovernanceProposal>(id, action, note);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::policy::RoleKind;
+
+    fn signed_by(account: usize) {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(account))
+            .predecessor_account_id(accounts(account))
+            .attached_deposit(10u128.pow(24))
+            .build());
+    }
+
+    /// a contract with accounts(0) and accounts(1) on the council
+    fn contract() -> Contract {
+        signed_by(0);
+        Contract::new(
+            "dao".to_string(),
+            "testing".to_string(),
+            vec![accounts(0), accounts(1)],
+            None,
+        )
+    }
+
+    /// submit a governance proposal as accounts(0)
+    fn propose(contract: &mut Contract, proposal: GovernanceProposal) -> u64 {
+        signed_by(0);
+        contract.add_payout_governance(PayoutInput {
+            description: "governance".to_string(),
+            information: proposal,
+        })
+    }
+
+    fn approve(contract: &mut Contract, id: u64, voters: &[usize]) {
+        for voter in voters {
+            signed_by(*voter);
+            contract.act_payout_governance(id, Action::VoteApprove, None);
+        }
+    }
+
+    #[test]
+    fn approved_proposal_changes_the_council() {
+        let mut contract = contract();
+        let id = propose(
+            &mut contract,
+            GovernanceProposal::AddCouncilMember {
+                account_id: accounts(2),
+            },
+        );
+        approve(&mut contract, id, &[0, 1]);
+        let proposal = contract.governance_proposals.get(&id).unwrap();
+        assert_eq!(proposal.status, PayoutStatus::Approved);
+        assert!(contract.members.is_council_member(&accounts(2)));
+        assert_eq!(contract.council_version, 1);
+    }
+
+    #[test]
+    fn removal_emptying_the_council_is_rejected() {
+        let mut contract = contract();
+        let first = propose(
+            &mut contract,
+            GovernanceProposal::RemoveCouncilMember {
+                account_id: accounts(0),
+            },
+        );
+        let second = propose(
+            &mut contract,
+            GovernanceProposal::RemoveCouncilMember {
+                account_id: accounts(1),
+            },
+        );
+        approve(&mut contract, first, &[0, 1]);
+        assert!(!contract.members.is_council_member(&accounts(0)));
+        // the remaining member approves the second removal, which would
+        // leave the DAO without a council
+        approve(&mut contract, second, &[1]);
+        let proposal = contract.governance_proposals.get(&second).unwrap();
+        assert_eq!(proposal.status, PayoutStatus::Rejected);
+        assert!(contract.members.is_council_member(&accounts(1)));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_POLICY")]
+    fn roles_locking_governance_are_rejected() {
+        let mut contract = contract();
+        let mut policy = contract.get_policy();
+        for role in policy.roles.iter_mut() {
+            if role.kind == RoleKind::Council {
+                role.permissions.remove("*:AddPayout");
+            }
+        }
+        propose(&mut contract, GovernanceProposal::ChangeRoles { policy });
+    }
+}
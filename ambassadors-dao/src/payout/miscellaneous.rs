@@ -1,4 +1,4 @@
-use near_sdk::{env, near_bindgen};
+use near_sdk::near_bindgen;
 
 use super::{types::Action, *};
 
@@ -26,25 +26,24 @@ pub enum Miscellaneous {
     },
 }
 
-impl From<PayoutInput<Miscellaneous>> for Payout<Miscellaneous> {
-    fn from(input: PayoutInput<Miscellaneous>) -> Self {
-        Self {
-            proposer: env::predecessor_account_id(),
-            description: input.description,
-            info: input.information,
-            status: PayoutStatus::UnderConsideration,
-            votes_count: VotesCount::new(),
-            votes: HashMap::default(),
-        }
+impl PayoutInfo for Miscellaneous {
+    const KIND: PayoutKind = PayoutKind::Miscellaneous;
+    const ERR_NOT_FOUND: &'static str = error::ERR_MISCELLANEOUS_NOT_FOUND;
+
+    fn payouts(contract: &Contract) -> &LookupMap<u64, Payout<Self>> {
+        &contract.miscellaneous
     }
-}
 
-#[near_bindgen]
-impl Contract {
-    /// create a miscellaneous payout
-    pub fn add_payout_miscellaneous(&mut self, payout: PayoutInput<Miscellaneous>) -> u64 {
-        // validate input
-        match &payout.information {
+    fn payouts_mut(contract: &mut Contract) -> &mut LookupMap<u64, Payout<Self>> {
+        &mut contract.miscellaneous
+    }
+
+    fn last_id(contract: &Contract) -> u64 {
+        contract.last_miscellaneous_id
+    }
+
+    fn validate(_contract: &mut Contract, _id: u64, info: &Self) {
+        match info {
             Miscellaneous::ContentCreationBounty {
                 links_to_content, ..
             } => {
@@ -63,51 +62,62 @@ impl Contract {
                     panic!("ERR_INVALID_LINKS_TO_PAYOUTS")
                 }
             }
+        }
+    }
+
+    fn unlink(_contract: &mut Contract, _id: u64, _info: &Self) {}
+
+    fn approved_transfers(
+        contract: &Contract,
+        _id: u64,
+        payout: &Payout<Self>,
+    ) -> Result<Vec<(AccountId, USD)>, String> {
+        let amounts = contract.internal_reward_schedule(payout.reward_schedule_version);
+        let amount = match payout.info {
+            Miscellaneous::ContentCreationBounty {
+                expected_amount, ..
+            } => expected_amount,
+            Miscellaneous::CampusAmbassadorBonus { .. } => amounts.ca_bonus,
+            Miscellaneous::CampusSigningMOU { .. } => amounts.campus_mou,
         };
+        Ok(vec![(payout.proposer.clone(), amount)])
+    }
+
+    fn on_approved(
+        _contract: &mut Contract,
+        _id: u64,
+        _payout: &Payout<Self>,
+        _transfers: &[(AccountId, USD)],
+    ) {
+    }
+}
 
-        // anyone can create this, no permission checks needed
+#[near_bindgen]
+impl Contract {
+    /// create a miscellaneous payout
+    #[payable]
+    pub fn add_payout_miscellaneous(&mut self, payout: PayoutInput<Miscellaneous>) -> u64 {
+        self.internal_assert_can_add_payout(&PayoutKind::Miscellaneous);
+        let bond = self.internal_take_bond();
+        // validate input
+        Miscellaneous::validate(self, self.last_miscellaneous_id + 1, &payout.information);
 
         // add the miscellaneous to Contract.miscellaneous
         let new_id = self.last_miscellaneous_id + 1;
-        self.miscellaneous.insert(&new_id, &Payout::from(payout));
+        self.miscellaneous.insert(
+            &new_id,
+            &Payout::new(
+                payout,
+                self.reward_schedule_version,
+                self.council_version,
+                bond,
+            ),
+        );
         self.last_miscellaneous_id = new_id;
         new_id
     }
     /// act on a miscellaneous payout
     pub fn act_payout_miscellaneous(&mut self, id: u64, action: Action, note: Option<String>) {
-        let mut misc = match self.miscellaneous.get(&id) {
-            Some(m) => m,
-            None => {
-                panic!("{}", error::ERR_MISCELLANEOUS_NOT_FOUND);
-            }
-        };
-        internal_act_payout(
-            self.members.is_council_member(&env::signer_account_id()),
-            self.members.get_council_size() as u64,
-            &mut misc,
-            action,
-            note,
-        );
-        self.miscellaneous.insert(&id, &misc);
-        if misc.status == PayoutStatus::Approved {
-            // get the exchange rate
-            let transfer = match misc.info {
-                Miscellaneous::ContentCreationBounty {
-                    expected_amount, ..
-                } => (misc.proposer, expected_amount),
-                Miscellaneous::CampusAmbassadorBonus { .. } => {
-                    (misc.proposer, amounts::CA_BONUS_AMOUNT)
-                }
-                Miscellaneous::CampusSigningMOU { .. } => {
-                    (misc.proposer, amounts::CAMPUS_MOU_AMOUNT)
-                }
-            };
-            self.get_exchange_rate().then(ext::make_transfers(
-                vec![transfer],
-                env::current_account_id(),
-                0,
-                Gas(types::ONE_TGAS),
-            ));
-        }
+        self.internal_act_on_payout::<Miscellaneous>(id, action, note);
     }
 }
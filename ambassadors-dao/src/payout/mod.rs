@@ -1,20 +1,36 @@
 use std::collections::HashMap;
 
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::collections::LookupMap;
 use near_sdk::env;
+use near_sdk::json_types::{U128, U64};
 use near_sdk::serde::{Deserialize, Serialize};
-use near_sdk::AccountId;
+use near_sdk::{AccountId, Balance, Promise};
 
-pub use bounty::{Bounty, BountyPayout};
+pub use amendment::Revision;
+pub use appeal::Appeal;
+pub use board::OpenBounty;
+pub use bounty::{Bounty, BountyPayout, CompletionClaim};
+pub use comments::Comment;
+pub use governance::{GovernancePayout, GovernanceProposal};
 pub use miscellaneous::{Miscellaneous, MiscellaneousPayout};
-pub use proposal::{Proposal, ProposalPayout};
+pub use payment::{PaymentRecord, GAS_FOR_MAKE_TRANSFERS, GAS_FOR_ON_PAYMENT_TRANSFERRED};
+pub use proposal::{Proposal, ProposalPayout, Tranche};
 pub use referrals::{Referral, ReferralPayout};
 
+use crate::policy::VoteRule;
 use crate::*;
 
+mod amendment;
+mod appeal;
+mod board;
 mod bounty;
+mod comments;
+mod governance;
 mod miscellaneous;
+mod payment;
 mod proposal;
+mod recount;
 mod referrals;
 
 /// The URL to any resource on the internet
@@ -30,6 +46,36 @@ pub struct SubmissionInfo {
     pub submission_link: ResourceLink,
 }
 
+/// The kinds of payouts, each stored in its own collection
+#[derive(
+    BorshSerialize,
+    BorshDeserialize,
+    Serialize,
+    Deserialize,
+    Clone,
+    PartialEq,
+    Eq,
+    PartialOrd,
+    Ord,
+    Hash,
+    Debug,
+)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PayoutKind {
+    Proposal,
+    Bounty,
+    Miscellaneous,
+    Referral,
+    Governance,
+}
+
+impl PayoutKind {
+    /// the name of the kind in the permissions of the policy
+    pub fn to_policy_label(&self) -> String {
+        format!("{:?}", self).to_lowercase()
+    }
+}
+
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub enum PayoutStatus {
@@ -37,6 +83,12 @@ pub enum PayoutStatus {
     Rejected,
     Removed(Option<String>),
     UnderConsideration,
+    /// the voting period ended before the payout was finalized
+    Expired,
+    /// the payout was marked as spam for the given reason and its bond forfeited
+    Spam(String),
+    /// the payout was vetoed for the given reason and its bond forfeited
+    Vetoed(String),
 }
 
 /// A generic input structure for payouts
@@ -53,6 +105,7 @@ pub struct PayoutInput<T: Serialize> {
 pub struct VotesCount {
     pub approve_count: u64,
     pub reject_count: u64,
+    pub abstain_count: u64,
 }
 
 impl VotesCount {
@@ -60,8 +113,30 @@ impl VotesCount {
         Self {
             approve_count: 0,
             reject_count: 0,
+            abstain_count: 0,
         }
     }
+
+    /// the number of votes cast, abstentions included
+    pub fn total(&self) -> u64 {
+        self.approve_count + self.reject_count + self.abstain_count
+    }
+
+    fn count_mut(&mut self, vote: &vote::Vote) -> &mut u64 {
+        match vote {
+            vote::Vote::Approve => &mut self.approve_count,
+            vote::Vote::Reject => &mut self.reject_count,
+            vote::Vote::Abstain => &mut self.abstain_count,
+        }
+    }
+
+    pub fn add(&mut self, vote: &vote::Vote) {
+        *self.count_mut(vote) += 1;
+    }
+
+    pub fn remove(&mut self, vote: &vote::Vote) {
+        *self.count_mut(vote) -= 1;
+    }
 }
 
 impl Default for VotesCount {
@@ -84,14 +159,152 @@ pub struct Payout<T: Serialize> {
     /// a brief description for the Payout
     pub description: String,
     /// the of individual votes on the Payout
-    pub votes: HashMap<AccountId, vote::Vote>,
+    pub votes: HashMap<AccountId, vote::VoteRecord>,
     /// the total vote count, updated whenever the votes are updated
     pub votes_count: VotesCount,
+    /// the block timestamp at which the Payout was submitted, or appealed
+    /// since the voting period of the appeal starts then
+    pub submission_time: U64,
+    /// the payments made once the Payout is approved
+    pub payments: Vec<PaymentRecord>,
+    /// the version of the reward schedule current at submission, used to
+    /// compute the amounts paid
+    pub reward_schedule_version: u64,
+    /// if approving the Payout exceeded the budget of its kind
+    pub over_budget: bool,
+    /// the deposit in yoctonear attached to the submission, refunded to the
+    /// proposer once the Payout is approved, rejected, expired or removed
+    pub bond: U128,
+    /// the council members that vetoed the Payout and their reasons
+    pub vetoes: HashMap<AccountId, String>,
+    /// the version of the council the votes were last counted with
+    pub council_version: u64,
+    /// the previous versions of the Payout, replaced by amendments
+    pub revisions: Vec<Revision<T>>,
+    /// the appeal of the proposer against the rejection of the Payout, if any
+    pub appeal: Option<Appeal>,
+}
+
+impl<T: Serialize> Payout<T> {
+    /// create a payout submitted by the caller under the given reward schedule
+    /// and council
+    pub fn new(
+        input: PayoutInput<T>,
+        reward_schedule_version: u64,
+        council_version: u64,
+        bond: Balance,
+    ) -> Self {
+        Self {
+            proposer: env::predecessor_account_id(),
+            description: input.description,
+            info: input.information,
+            status: PayoutStatus::UnderConsideration,
+            votes_count: VotesCount::new(),
+            votes: HashMap::default(),
+            submission_time: U64(env::block_timestamp()),
+            payments: Vec::new(),
+            reward_schedule_version,
+            over_budget: false,
+            bond: U128(bond),
+            vetoes: HashMap::new(),
+            council_version,
+            revisions: Vec::new(),
+            appeal: None,
+        }
+    }
+
+    /// record the vote of an account, replacing its previous vote
+    pub fn record_vote(&mut self, voter: AccountId, record: vote::VoteRecord) {
+        self.votes_count.add(&record.vote);
+        if let Some(previous) = self.votes.insert(voter, record) {
+            self.votes_count.remove(&previous.vote);
+        }
+    }
+
+    /// remove the vote of an account, if any
+    pub fn remove_vote(&mut self, voter: &AccountId) -> Option<vote::VoteRecord> {
+        let previous = self.votes.remove(voter)?;
+        self.votes_count.remove(&previous.vote);
+        Some(previous)
+    }
+
+    /// if the voting period of the rule has passed since the submission
+    pub fn is_expired(&self, rule: &VoteRule) -> bool {
+        env::block_timestamp() >= self.submission_time.0.saturating_add(rule.voting_period.0)
+    }
+
+    /// record the status of a finalized payout as the outcome of its appeal
+    pub fn record_appeal_outcome(&mut self) {
+        if self.status == PayoutStatus::UnderConsideration {
+            return;
+        }
+        if let Some(appeal) = self.appeal.as_mut() {
+            if appeal.outcome.is_none() {
+                appeal.outcome = Some(self.status.clone());
+            }
+        }
+    }
+
+    /// if the payout is under consideration and can still be approved
+    pub fn is_pending(&self, rule: &VoteRule) -> bool {
+        self.status == PayoutStatus::UnderConsideration && !self.is_expired(rule)
+    }
+}
+
+/// Implemented by the information of each kind of payout, gives generic
+/// access to the collection that the payouts of the kind are stored in.
+pub(crate) trait PayoutInfo: Serialize + BorshSerialize + BorshDeserialize + Sized {
+    const KIND: PayoutKind;
+    const ERR_NOT_FOUND: &'static str;
+
+    fn payouts(contract: &Contract) -> &LookupMap<u64, Payout<Self>>;
+
+    fn payouts_mut(contract: &mut Contract) -> &mut LookupMap<u64, Payout<Self>>;
+
+    /// the id of the last payout of this kind
+    fn last_id(contract: &Contract) -> u64;
+
+    /// Check the information of a payout submitted or amended by the caller
+    /// under the given id, panics if it is invalid. Links the payout to the
+    /// records it refers to.
+    fn validate(contract: &mut Contract, id: u64, info: &Self);
+
+    /// undo the links made by `validate` before the payout is amended
+    fn unlink(contract: &mut Contract, id: u64, info: &Self);
+
+    /// the transfers to make once a payout of this kind is approved, or the
+    /// reason the approved payout can no longer be executed
+    fn approved_transfers(
+        contract: &Contract,
+        id: u64,
+        payout: &Payout<Self>,
+    ) -> Result<Vec<(AccountId, USD)>, String>;
+
+    /// apply the effects of an approved payout of this kind, once its
+    /// transfers are within the budget
+    fn on_approved(
+        contract: &mut Contract,
+        id: u64,
+        payout: &Payout<Self>,
+        transfers: &[(AccountId, USD)],
+    );
+}
+
+/// The account taking an action on a payout and what it can do
+pub(crate) struct Actor {
+    pub account_id: AccountId,
+    /// if the policy allows the account to take the action
+    pub is_allowed: bool,
+    pub is_council_member: bool,
+    /// the council members who delegated their vote to the account and can
+    /// take the action
+    pub delegators: Vec<AccountId>,
 }
 
 pub(crate) fn internal_act_payout<T: Serialize>(
-    is_council_member: bool,
+    actor: Actor,
     council_size: u64,
+    rule: &VoteRule,
     payout: &mut Payout<T>,
     action: types::Action,
     note: Option<String>,
@@ -101,10 +314,49 @@ pub(crate) fn internal_act_payout<T: Serialize>(
         PayoutStatus::UnderConsideration => {}
         _ => panic!("{}: {}", error::ERR_NOT_PERMITTED, "payout finalized"),
     }
+    // the voting period is over, the action is not taken
+    if payout.is_expired(rule) {
+        env::log_str("Payout expired before it was finalized");
+        payout.status = PayoutStatus::Expired;
+        return;
+    }
 
-    let signer = env::signer_account_id();
+    let signer = actor.account_id;
 
+    // check if the user is authorized to take the action, council members
+    // can veto together without being allowed to veto on their own
+    let is_council_veto = matches!(action, types::Action::Veto) && actor.is_council_member;
+    if !actor.is_allowed && !is_council_veto {
+        panic!("{}", error::ERR_NOT_PERMITTED);
+    }
     match action {
+        types::Action::AddPayout | types::Action::PublishBounty => {
+            panic!("{}", error::ERR_INVALID_ACTION);
+        }
+        types::Action::Veto => {
+            let reason = match note {
+                Some(reason) if !reason.trim().is_empty() => reason,
+                _ => panic!("{}", error::ERR_VETO_REASON_REQUIRED),
+            };
+            if actor.is_allowed {
+                payout.status = PayoutStatus::Vetoed(reason);
+            } else {
+                if payout.vetoes.insert(signer, reason.clone()).is_some() {
+                    panic!("{}: {}", error::ERR_NOT_PERMITTED, "already vetoed");
+                }
+                if rule.is_vetoed(payout.vetoes.len() as u64, council_size) {
+                    payout.status = PayoutStatus::Vetoed(reason);
+                }
+            }
+        }
+        types::Action::MarkSpam => {
+            let reason = match note {
+                Some(reason) if !reason.trim().is_empty() => reason,
+                _ => panic!("{}", error::ERR_SPAM_REASON_REQUIRED),
+            };
+            env::log_str("Payout marked as spam, its bond is forfeited");
+            payout.status = PayoutStatus::Spam(reason);
+        }
         types::Action::RemovePayout => {
             // only the proposer can remove the payout
             if signer != payout.proposer {
@@ -124,60 +376,381 @@ pub(crate) fn internal_act_payout<T: Serialize>(
                 }
             };
         }
-        types::Action::VoteReject => {
-            // check if the user is authorized to take the action
-            if !is_council_member {
-                panic!("{}", error::ERR_NOT_PERMITTED);
+        types::Action::VoteApprove | types::Action::VoteReject | types::Action::VoteAbstain => {
+            let vote = vote::Vote::from(action);
+            // any vote can be commented, and a rejection must tell the proposer why
+            let comment = note.filter(|comment| !comment.trim().is_empty());
+            match &comment {
+                Some(comment) => comments::assert_valid_comment(comment),
+                None if vote == vote::Vote::Reject => {
+                    panic!("{}", error::ERR_REJECTION_REASON_REQUIRED)
+                }
+                None => {}
             }
-            // if the signer has already voted
-            if payout.votes.contains_key(&signer) {
+            // a council member can change their vote while the payout is under
+            // consideration, the previous vote is no longer counted
+            let own_vote = vote::VoteRecord {
+                vote: vote.clone(),
+                delegate: None,
+                comment: comment.clone(),
+            };
+            if payout.votes.get(&signer) == Some(&own_vote) {
                 panic!("{}: {}", error::ERR_NOT_PERMITTED, "already voted");
             }
             // one may think we need to check if the count is consistent with
             // the number of council members, but just checking if the signer
             // council member has voted or not rules out the said issue
-            payout.votes.insert(signer, vote::Vote::from(action));
-            payout.votes_count.reject_count += 1;
+            payout.record_vote(signer.clone(), own_vote);
+            // the vote is also cast for the delegators who did not vote themselves
+            for delegator in actor.delegators {
+                let has_voted = payout
+                    .votes
+                    .get(&delegator)
+                    .is_some_and(|record| record.delegate.is_none());
+                if !has_voted {
+                    payout.record_vote(
+                        delegator,
+                        vote::VoteRecord {
+                            vote: vote.clone(),
+                            delegate: Some(signer.clone()),
+                            comment: comment.clone(),
+                        },
+                    );
+                }
+            }
             // update payout status if needed
-            internal_update_payout_status(council_size, payout);
+            internal_update_payout_status(council_size, rule, payout);
         }
-        types::Action::VoteApprove => {
-            // check if the user is authorized to take the action
-            if !is_council_member {
-                panic!("{}", error::ERR_NOT_PERMITTED);
+        types::Action::RetractVote => {
+            if payout.remove_vote(&signer).is_none() {
+                panic!("{}: {}", error::ERR_NOT_PERMITTED, "not voted");
             }
-            // if the signer has already voted
-            if payout.votes.contains_key(&signer) {
-                panic!("{}: {}", error::ERR_NOT_PERMITTED, "already voted");
+            // the votes cast on behalf of delegators are retracted as well
+            let delegated: Vec<AccountId> = payout
+                .votes
+                .iter()
+                .filter(|(_, record)| record.delegate.as_ref() == Some(&signer))
+                .map(|(voter, _)| voter.clone())
+                .collect();
+            for voter in delegated {
+                payout.remove_vote(&voter);
             }
-            // one may think we need to check if the count is consistent with
-            // the number of council members, but just checking if the signer
-            // council member has voted or not rules out the said issue
-            payout.votes.insert(signer, vote::Vote::from(action));
-            payout.votes_count.approve_count += 1;
-            // update payout status if needed
-            internal_update_payout_status(council_size, payout);
         }
     };
 }
 
+/// Refund the bond of a payout that was just approved, rejected, expired or
+/// removed by its proposer.
+/// Must only be called when the status of the payout changed to a final one.
+pub(crate) fn internal_refund_bond<T: Serialize>(payout: &Payout<T>) {
+    let is_refunded = matches!(
+        payout.status,
+        PayoutStatus::Approved
+            | PayoutStatus::Rejected
+            | PayoutStatus::Expired
+            | PayoutStatus::Removed(_)
+    );
+    if is_refunded && payout.bond.0 > 0 {
+        Promise::new(payout.proposer.clone()).transfer(payout.bond.0);
+    }
+}
+
 /// check the votes on a payout and update the status if needed
 pub(crate) fn internal_update_payout_status<T: Serialize>(
     council_size: u64,
+    rule: &VoteRule,
     payout: &mut Payout<T>,
 ) {
-    let approve_count = payout.votes_count.approve_count;
-    let reject_count = payout.votes_count.reject_count;
-    let sum = approve_count + reject_count;
-
-    // let's check if all council members have voted
-    if sum == council_size {
-        // if approve_count / sum >= 1/2     (i.e. at least half votes are approve)
-        // therefore, 2 * approve_count >= sum
-        if 2 * approve_count >= sum {
-            payout.status = PayoutStatus::Approved;
+    if let Some(status) = rule.evaluate(council_size, &payout.votes_count) {
+        payout.status = status;
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Finalize a payout under consideration with the current voting policy.
+    /// A payout is marked expired once its voting period is over, the votes
+    /// then no longer count. Anyone can call this.
+    pub fn finalize_payout(&mut self, kind: PayoutKind, id: u64) {
+        match kind {
+            PayoutKind::Proposal => self.internal_finalize_payout::<Proposal>(id),
+            PayoutKind::Bounty => self.internal_finalize_payout::<Bounty>(id),
+            PayoutKind::Miscellaneous => self.internal_finalize_payout::<Miscellaneous>(id),
+            PayoutKind::Referral => self.internal_finalize_payout::<Referral>(id),
+            PayoutKind::Governance => self.internal_finalize_payout::<GovernanceProposal>(id),
+        }
+    }
+}
+
+impl Contract {
+    /// act on a payout and make the transfers if it got approved
+    pub(crate) fn internal_act_on_payout<T: PayoutInfo>(
+        &mut self,
+        id: u64,
+        action: types::Action,
+        note: Option<String>,
+    ) {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        // the council changed since the last count, the action is not taken
+        // if the recount finalizes the payout
+        if self.internal_recount_payout(id, &mut payout)
+            && payout.status != PayoutStatus::UnderConsideration
+        {
+            env::log_str("Payout finalized by the recount of its votes");
+            T::payouts_mut(self).insert(&id, &payout);
+            return;
+        }
+        let signer = env::signer_account_id();
+        let actor = Actor {
+            is_allowed: self.internal_is_allowed(&T::KIND, &action),
+            is_council_member: self.members.is_council_member(&signer),
+            delegators: self.internal_active_delegators(&signer, &T::KIND, &action),
+            account_id: signer,
+        };
+        internal_act_payout(
+            actor,
+            self.members.get_council_size() as u64,
+            self.vote_policy.rule_for(&T::KIND),
+            &mut payout,
+            action,
+            note,
+        );
+        // check if payout state is approved
+        if payout.status == PayoutStatus::Approved {
+            self.internal_execute_payout(id, &mut payout);
+        }
+        payout.record_appeal_outcome();
+        internal_refund_bond(&payout);
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+
+    fn internal_finalize_payout<T: PayoutInfo>(&mut self, id: u64) {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        if payout.status != PayoutStatus::UnderConsideration {
+            panic!("{}: {}", error::ERR_NOT_PERMITTED, "payout finalized");
+        }
+        if self.internal_recount_payout(id, &mut payout)
+            && payout.status != PayoutStatus::UnderConsideration
+        {
+            T::payouts_mut(self).insert(&id, &payout);
+            return;
+        }
+        let rule = self.vote_policy.rule_for(&T::KIND);
+        if payout.is_expired(rule) {
+            payout.status = PayoutStatus::Expired;
         } else {
-            payout.status = PayoutStatus::Rejected;
+            internal_update_payout_status(
+                self.members.get_council_size() as u64,
+                rule,
+                &mut payout,
+            );
+            if payout.status == PayoutStatus::UnderConsideration {
+                panic!("{}: {}", error::ERR_NOT_PERMITTED, "voting period not over");
+            }
+        }
+        if payout.status == PayoutStatus::Approved {
+            self.internal_execute_payout(id, &mut payout);
+        }
+        payout.record_appeal_outcome();
+        internal_refund_bond(&payout);
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+
+    /// Mark a payout still under consideration once its voting period is over
+    /// as expired, refunding its bond. Returns the status of the payout.
+    pub(crate) fn internal_expire_payout<T: PayoutInfo>(&mut self, id: u64) -> PayoutStatus {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        let rule = self.vote_policy.rule_for(&T::KIND);
+        if payout.status == PayoutStatus::UnderConsideration && payout.is_expired(rule) {
+            payout.status = PayoutStatus::Expired;
+            payout.record_appeal_outcome();
+            internal_refund_bond(&payout);
+            T::payouts_mut(self).insert(&id, &payout);
+        }
+        payout.status
+    }
+
+    /// Panics if the attached deposit does not cover the submission bond,
+    /// returns the attached deposit which is kept as the bond.
+    pub(crate) fn internal_take_bond(&self) -> Balance {
+        let deposit = env::attached_deposit();
+        if deposit < self.config.submission_bond.0 {
+            panic!(
+                "{}: {}",
+                error::ERR_NOT_ENOUGH_BOND,
+                self.config.submission_bond.0
+            );
         }
+        deposit
+    }
+}
+
+/// Payouts and contracts shared by the tests of the payout modules.
+#[cfg(test)]
+pub(crate) mod fixtures {
+    use near_sdk::test_utils::accounts;
+
+    use super::*;
+
+    /// an open proposal of the predecessor with a budget of $10
+    pub(crate) fn open_proposal() -> ProposalPayout {
+        Payout::new(
+            PayoutInput {
+                description: "open proposal".to_string(),
+                information: Proposal::Open {
+                    estimated_budget: USD::from_dollars(10),
+                    supporting_document: "https://example.com/doc.pdf".to_string(),
+                    milestones: None,
+                },
+            },
+            0,
+            0,
+            0,
+        )
+    }
+
+    /// a contract with accounts(0) as the council and the given proposal at id 1
+    pub(crate) fn contract_with_proposal(payout: ProposalPayout) -> Contract {
+        let mut contract = Contract::new(
+            "dao".to_string(),
+            "testing".to_string(),
+            vec![accounts(0)],
+            None,
+        );
+        contract.proposals.insert(&1, &payout);
+        contract.last_proposal_id = 1;
+        contract
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::policy::VotePolicy;
+    use crate::types::Action;
+
+    const COUNCIL_SIZE: u64 = 5;
+
+    fn payout() -> Payout<String> {
+        testing_env!(VMContextBuilder::new()
+            .predecessor_account_id(accounts(5))
+            .build());
+        Payout::new(
+            PayoutInput {
+                description: "payout".to_string(),
+                information: "info".to_string(),
+            },
+            0,
+            0,
+            0,
+        )
+    }
+
+    fn act(payout: &mut Payout<String>, voter: usize, delegators: &[usize], action: Action) {
+        let actor = Actor {
+            account_id: accounts(voter),
+            is_allowed: true,
+            is_council_member: true,
+            delegators: delegators.iter().map(|d| accounts(*d)).collect(),
+        };
+        let note = Some("reason".to_string());
+        let rule = VotePolicy::default().default_rule;
+        internal_act_payout(actor, COUNCIL_SIZE, &rule, payout, action, note);
+    }
+
+    /// check the vote counts match the recorded votes
+    fn assert_counts_consistent(payout: &Payout<String>) {
+        let mut count = VotesCount::new();
+        payout
+            .votes
+            .values()
+            .for_each(|record| count.add(&record.vote));
+        assert_eq!(payout.votes_count.approve_count, count.approve_count);
+        assert_eq!(payout.votes_count.reject_count, count.reject_count);
+        assert_eq!(payout.votes_count.abstain_count, count.abstain_count);
+    }
+
+    #[test]
+    fn changing_a_vote_replaces_it() {
+        let mut payout = payout();
+        act(&mut payout, 0, &[], Action::VoteApprove);
+        act(&mut payout, 0, &[], Action::VoteReject);
+        assert_counts_consistent(&payout);
+        assert_eq!(payout.votes_count.total(), 1);
+        assert_eq!(payout.votes_count.reject_count, 1);
+        act(&mut payout, 0, &[], Action::VoteAbstain);
+        assert_counts_consistent(&payout);
+        assert_eq!(payout.votes_count.abstain_count, 1);
+        act(&mut payout, 0, &[], Action::RetractVote);
+        assert_counts_consistent(&payout);
+        assert_eq!(payout.votes_count.total(), 0);
+    }
+
+    #[test]
+    fn delegated_votes_are_counted_once() {
+        let mut payout = payout();
+        // 1 votes for 2 and 3, then 2 votes themselves
+        act(&mut payout, 1, &[2, 3], Action::VoteApprove);
+        assert_eq!(payout.votes_count.approve_count, 3);
+        act(&mut payout, 2, &[], Action::VoteReject);
+        assert_counts_consistent(&payout);
+        assert_eq!(payout.votes_count.approve_count, 2);
+        assert_eq!(payout.votes_count.reject_count, 1);
+        // the own vote of 2 is kept when 1 changes their vote
+        act(&mut payout, 1, &[2, 3], Action::VoteAbstain);
+        assert_counts_consistent(&payout);
+        assert_eq!(payout.votes_count.abstain_count, 2);
+        assert_eq!(payout.votes_count.reject_count, 1);
+        // retracting removes the votes cast for 3 but not the own vote of 2
+        act(&mut payout, 1, &[2, 3], Action::RetractVote);
+        assert_counts_consistent(&payout);
+        assert_eq!(payout.votes_count.total(), 1);
+        assert_eq!(payout.votes_count.reject_count, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_REJECTION_REASON_REQUIRED")]
+    fn rejection_requires_a_reason() {
+        let mut payout = payout();
+        let actor = Actor {
+            account_id: accounts(0),
+            is_allowed: true,
+            is_council_member: true,
+            delegators: vec![],
+        };
+        let rule = VotePolicy::default().default_rule;
+        internal_act_payout(
+            actor,
+            COUNCIL_SIZE,
+            &rule,
+            &mut payout,
+            Action::VoteReject,
+            None,
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_SPAM_REASON_REQUIRED")]
+    fn marking_spam_requires_a_reason() {
+        let mut payout = payout();
+        let actor = Actor {
+            account_id: accounts(0),
+            is_allowed: true,
+            is_council_member: false,
+            delegators: vec![],
+        };
+        let rule = VotePolicy::default().default_rule;
+        let note = Some(" ".to_string());
+        internal_act_payout(
+            actor,
+            COUNCIL_SIZE,
+            &rule,
+            &mut payout,
+            Action::MarkSpam,
+            note,
+        );
     }
 }
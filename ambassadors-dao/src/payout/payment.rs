@@ -0,0 +1,604 @@
+// the functions generated for `ext_self` take the account, deposit and gas on top of the arguments
+#![allow(clippy::too_many_arguments)]
+
+use near_contract_standards::fungible_token::core_impl::ext_fungible_token;
+use near_sdk::json_types::U128;
+use near_sdk::serde_json;
+use near_sdk::{env, ext_contract, near_bindgen, Balance, Promise, PromiseResult};
+
+use super::*;
+use crate::asset::PayoutAsset;
+use crate::budget::BudgetStatus;
+
+/// Gas for make_transfers to run, without the gas attached to the payment callbacks
+pub const GAS_FOR_MAKE_TRANSFERS: u64 = types::ONE_TGAS * 5;
+/// Gas for the callback resolving a single payment
+pub const GAS_FOR_ON_PAYMENT_TRANSFERRED: u64 = types::ONE_TGAS * 5;
+/// Gas for registering the recipient of a stablecoin payment with the token
+pub const GAS_FOR_STORAGE_DEPOSIT: u64 = types::ONE_TGAS * 5;
+/// Gas for transferring a stablecoin payment
+pub const GAS_FOR_FT_TRANSFER: u64 = types::ONE_TGAS * 10;
+/// Deposit attached to register the recipient of a stablecoin payment with
+/// the token, the token refunds it if the recipient is already registered
+pub const STORAGE_DEPOSIT_FOR_FT: Balance = 1_250_000_000_000_000_000_000;
+/// Number of blocks after which a pending payment whose transfer was never
+/// issued is considered stuck and can be retried
+pub const PAYMENT_TIMEOUT_BLOCKS: u64 = 500;
+
+#[ext_contract(ext_storage_management)]
+pub trait StorageManagement {
+    fn storage_deposit(&mut self, account_id: Option<AccountId>, registration_only: Option<bool>);
+}
+
+#[ext_contract(ext_self)]
+pub trait PaymentCallbacks {
+    fn on_payment_transferred(
+        &mut self,
+        kind: PayoutKind,
+        id: u64,
+        index: u64,
+        amount_yocto: U128,
+        rate: USD,
+    );
+}
+
+/// The state of a transfer made for an approved payout
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, PartialEq, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub enum PaymentStatus {
+    /// the transfer has been scheduled but not resolved yet
+    Pending,
+    /// the transfer went through
+    Paid {
+        /// the amount transferred in the smallest unit of the asset,
+        /// yoctonear for NEAR
+        amount_yocto: U128,
+        /// the exchange rate in dollars per unit of the asset used to
+        /// convert the USD amount, $1 for a stablecoin
+        rate: USD,
+        /// the height of the block in which the transfer was resolved
+        block: u64,
+    },
+    /// the transfer could not be made
+    Failed { reason: String },
+}
+
+/// A payment to a single recipient of a payout
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[serde(crate = "near_sdk::serde")]
+pub struct PaymentRecord {
+    /// the recipient of the payment
+    pub account_id: AccountId,
+    /// the amount to pay in USD
+    pub amount: USD,
+    /// the asset the payment is made in
+    pub asset: PayoutAsset,
+    /// the current state of the payment
+    pub status: PaymentStatus,
+    /// the height of the block in which the payment was last scheduled
+    pub scheduled_at: u64,
+    /// if the transfer of the pending payment has been issued
+    pub transfer_issued: bool,
+}
+
+impl PaymentRecord {
+    /// A payment can be retried if its transfer failed, or if it is pending
+    /// without its transfer ever being issued for longer than the timeout.
+    /// A payment with an issued transfer is never retried, so it is never paid twice.
+    pub fn is_retryable(&self) -> bool {
+        match self.status {
+            PaymentStatus::Failed { .. } => true,
+            PaymentStatus::Pending => {
+                !self.transfer_issued
+                    && env::block_height() > self.scheduled_at + PAYMENT_TIMEOUT_BLOCKS
+            }
+            PaymentStatus::Paid { .. } => false,
+        }
+    }
+}
+
+/// A failed payment, as listed by `get_failed_payments`
+#[derive(Serialize, Deserialize)]
+#[serde(crate = "near_sdk::serde")]
+pub struct FailedPayment {
+    /// the id of the payout
+    pub id: u64,
+    /// the index of the payment in the payments of the payout
+    pub index: u64,
+    #[serde(flatten)]
+    pub payment: PaymentRecord,
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Transfer the pending payments of a payout scheduled at the given block
+    /// using the exchange rate returned by the oracle. If the exchange rate is
+    /// unavailable, malformed or rejected by the rate limits, the next oracle
+    /// is queried, and if there is none left the payments are marked failed.
+    #[private]
+    pub fn make_transfers(
+        &mut self,
+        kind: PayoutKind,
+        id: u64,
+        scheduled_at: u64,
+        provider_index: u64,
+    ) {
+        // the result is parsed here rather than by the generated callback code,
+        // which would panic on a malformed rate instead of trying the next oracle
+        let rate = match env::promise_result(0) {
+            PromiseResult::Successful(value) => serde_json::from_slice::<OracleRate>(&value)
+                .map_err(|_| "malformed exchange rate".to_string())
+                .and_then(|rate| self.oracle.rate_limits.validate(&rate)),
+            _ => Err("exchange rate unavailable".to_string()),
+        };
+        match kind {
+            PayoutKind::Proposal => {
+                self.internal_make_transfers::<Proposal>(id, scheduled_at, provider_index, rate)
+            }
+            PayoutKind::Bounty => {
+                self.internal_make_transfers::<Bounty>(id, scheduled_at, provider_index, rate)
+            }
+            PayoutKind::Miscellaneous => self.internal_make_transfers::<Miscellaneous>(
+                id,
+                scheduled_at,
+                provider_index,
+                rate,
+            ),
+            PayoutKind::Referral => {
+                self.internal_make_transfers::<Referral>(id, scheduled_at, provider_index, rate)
+            }
+            PayoutKind::Governance => self.internal_make_transfers::<GovernanceProposal>(
+                id,
+                scheduled_at,
+                provider_index,
+                rate,
+            ),
+        }
+    }
+
+    /// Retry the failed or stuck payments of an approved payout.
+    /// Can only be done by a council member
+    pub fn retry_payment(&mut self, kind: PayoutKind, id: u64) {
+        if !self.members.is_council_member(&env::signer_account_id()) {
+            panic!("{}", error::ERR_NOT_PERMITTED);
+        }
+        match kind {
+            PayoutKind::Proposal => self.internal_retry_payment::<Proposal>(id),
+            PayoutKind::Bounty => self.internal_retry_payment::<Bounty>(id),
+            PayoutKind::Miscellaneous => self.internal_retry_payment::<Miscellaneous>(id),
+            PayoutKind::Referral => self.internal_retry_payment::<Referral>(id),
+            PayoutKind::Governance => self.internal_retry_payment::<GovernanceProposal>(id),
+        }
+    }
+
+    /// Record the result of a transfer made for a payout.
+    #[private]
+    pub fn on_payment_transferred(
+        &mut self,
+        kind: PayoutKind,
+        id: u64,
+        index: u64,
+        amount_yocto: U128,
+        rate: USD,
+    ) {
+        let status = match env::promise_result(0) {
+            PromiseResult::Successful(_) => PaymentStatus::Paid {
+                amount_yocto,
+                rate,
+                block: env::block_height(),
+            },
+            _ => PaymentStatus::Failed {
+                reason: "transfer failed".to_string(),
+            },
+        };
+        match kind {
+            PayoutKind::Proposal => self.internal_set_payment_status::<Proposal>(id, index, status),
+            PayoutKind::Bounty => self.internal_set_payment_status::<Bounty>(id, index, status),
+            PayoutKind::Miscellaneous => {
+                self.internal_set_payment_status::<Miscellaneous>(id, index, status)
+            }
+            PayoutKind::Referral => self.internal_set_payment_status::<Referral>(id, index, status),
+            PayoutKind::Governance => {
+                self.internal_set_payment_status::<GovernanceProposal>(id, index, status)
+            }
+        }
+    }
+
+    /// Get the payments of a payout.
+    pub fn get_payments(&self, kind: PayoutKind, id: u64) -> Vec<PaymentRecord> {
+        match kind {
+            PayoutKind::Proposal => self.internal_get_payments::<Proposal>(id),
+            PayoutKind::Bounty => self.internal_get_payments::<Bounty>(id),
+            PayoutKind::Miscellaneous => self.internal_get_payments::<Miscellaneous>(id),
+            PayoutKind::Referral => self.internal_get_payments::<Referral>(id),
+            PayoutKind::Governance => self.internal_get_payments::<GovernanceProposal>(id),
+        }
+    }
+
+    /// Get the failed payments of the payouts of a kind in paginated view.
+    pub fn get_failed_payments(
+        &self,
+        kind: PayoutKind,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<FailedPayment> {
+        match kind {
+            PayoutKind::Proposal => {
+                self.internal_get_failed_payments::<Proposal>(from_index, limit)
+            }
+            PayoutKind::Bounty => self.internal_get_failed_payments::<Bounty>(from_index, limit),
+            PayoutKind::Miscellaneous => {
+                self.internal_get_failed_payments::<Miscellaneous>(from_index, limit)
+            }
+            PayoutKind::Referral => {
+                self.internal_get_failed_payments::<Referral>(from_index, limit)
+            }
+            PayoutKind::Governance => {
+                self.internal_get_failed_payments::<GovernanceProposal>(from_index, limit)
+            }
+        }
+    }
+}
+
+/// the number of NEAR payments of a payout scheduled at the given block
+fn num_of_near_transfers<T: Serialize>(payout: &Payout<T>, scheduled_at: u64) -> u64 {
+    payout
+        .payments
+        .iter()
+        .filter(|p| p.scheduled_at == scheduled_at && p.asset == PayoutAsset::Near)
+        .count() as u64
+}
+
+impl Contract {
+    /// Record the payments of an approved payout as pending and schedule the
+    /// transfers. A payout whose budget blocks the approval is put back under
+    /// consideration, and a payout that can no longer be executed is rejected.
+    pub(crate) fn internal_execute_payout<T: PayoutInfo>(
+        &mut self,
+        id: u64,
+        payout: &mut Payout<T>,
+    ) {
+        let asset = self.payout_assets.asset_for(&T::KIND).clone();
+        let transfers = match T::approved_transfers(self, id, payout) {
+            Ok(transfers) => transfers,
+            Err(reason) => {
+                env::log_str(&format!("Payout rejected: {}", reason));
+                payout.status = PayoutStatus::Rejected;
+                return;
+            }
+        };
+        let total = transfers
+            .iter()
+            .try_fold(USD(0), |total, (_, amount)| total.checked_add(*amount))
+            .expect(error::ERR_AMOUNT_OVERFLOW);
+        match self.internal_spend_budget(&T::KIND, total) {
+            BudgetStatus::Blocked => {
+                payout.status = PayoutStatus::UnderConsideration;
+                return;
+            }
+            status => payout.over_budget = status == BudgetStatus::Exceeded,
+        }
+        T::on_approved(self, id, payout, &transfers);
+        payout.payments = transfers
+            .into_iter()
+            .map(|(account_id, amount)| PaymentRecord {
+                account_id,
+                amount,
+                asset: asset.clone(),
+                status: PaymentStatus::Pending,
+                scheduled_at: env::block_height(),
+                transfer_issued: false,
+            })
+            .collect();
+        if !payout.payments.is_empty() {
+            self.internal_schedule_transfers(id, payout);
+        }
+    }
+
+    fn internal_retry_payment<T: PayoutInfo>(&mut self, id: u64) {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        if payout.status != PayoutStatus::Approved {
+            panic!("{}: {}", error::ERR_NOT_PERMITTED, "payout not approved");
+        }
+        let mut num_of_retries = 0;
+        for payment in payout.payments.iter_mut().filter(|p| p.is_retryable()) {
+            payment.status = PaymentStatus::Pending;
+            payment.scheduled_at = env::block_height();
+            payment.transfer_issued = false;
+            num_of_retries += 1;
+        }
+        if num_of_retries == 0 {
+            panic!("{}: {}", error::ERR_NOT_PERMITTED, "no payment to retry");
+        }
+        self.internal_schedule_transfers(id, &mut payout);
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+
+    /// Transfer the stablecoin payments scheduled in this block, and get the
+    /// exchange rate to transfer the NEAR payments scheduled in this block.
+    fn internal_schedule_transfers<T: PayoutInfo>(&self, id: u64, payout: &mut Payout<T>) {
+        let scheduled_at = env::block_height();
+        for (index, payment) in payout.payments.iter_mut().enumerate() {
+            if payment.scheduled_at != scheduled_at {
+                continue;
+            }
+            if let PayoutAsset::Stablecoin { token_id, decimals } = &payment.asset {
+                let amount = PayoutAsset::usd_to_token_amount(payment.amount, *decimals);
+                payment.transfer_issued = true;
+                ext_storage_management::storage_deposit(
+                    Some(payment.account_id.clone()),
+                    Some(true),
+                    token_id.clone(),
+                    STORAGE_DEPOSIT_FOR_FT,
+                    Gas(GAS_FOR_STORAGE_DEPOSIT),
+                )
+                .then(ext_fungible_token::ft_transfer(
+                    payment.account_id.clone(),
+                    U128(amount),
+                    Some(format!("{:?} payout {}", T::KIND, id)),
+                    token_id.clone(),
+                    1,
+                    Gas(GAS_FOR_FT_TRANSFER),
+                ))
+                .then(ext_self::on_payment_transferred(
+                    T::KIND,
+                    id,
+                    index as u64,
+                    U128(amount),
+                    USD::from_dollars(1),
+                    env::current_account_id(),
+                    0,
+                    Gas(GAS_FOR_ON_PAYMENT_TRANSFERRED),
+                ));
+            }
+        }
+        let num_of_transfers = num_of_near_transfers(payout, scheduled_at);
+        if num_of_transfers > 0 {
+            self.internal_query_oracle(0, T::KIND, id, scheduled_at, num_of_transfers);
+        }
+    }
+
+    fn internal_make_transfers<T: PayoutInfo>(
+        &mut self,
+        id: u64,
+        scheduled_at: u64,
+        provider_index: u64,
+        rate: Result<USD, String>,
+    ) {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        // try the next oracle if there is one
+        if let Err(reason) = &rate {
+            let next_index = provider_index + 1;
+            if (next_index as usize) < self.oracle.providers.len() {
+                env::log_str(&format!(
+                    "Oracle {} failed: {}, trying the next oracle",
+                    self.oracle.providers[provider_index as usize].account_id, reason
+                ));
+                self.internal_query_oracle(
+                    next_index,
+                    T::KIND,
+                    id,
+                    scheduled_at,
+                    num_of_near_transfers(&payout, scheduled_at),
+                );
+                return;
+            }
+        }
+        for (index, payment) in payout.payments.iter_mut().enumerate() {
+            // only the NEAR payments of this schedule that have not been transferred yet
+            if payment.status != PaymentStatus::Pending
+                || payment.scheduled_at != scheduled_at
+                || payment.transfer_issued
+                || payment.asset != PayoutAsset::Near
+            {
+                continue;
+            }
+            match rate {
+                Ok(rate) => {
+                    let amount_yocto = usd_to_balance(payment.amount, rate);
+                    payment.transfer_issued = true;
+                    Promise::new(payment.account_id.clone())
+                        .transfer(amount_yocto)
+                        .then(ext_self::on_payment_transferred(
+                            T::KIND,
+                            id,
+                            index as u64,
+                            U128(amount_yocto),
+                            rate,
+                            env::current_account_id(),
+                            0,
+                            Gas(GAS_FOR_ON_PAYMENT_TRANSFERRED),
+                        ));
+                }
+                Err(ref reason) => {
+                    payment.status = PaymentStatus::Failed {
+                        reason: reason.clone(),
+                    };
+                }
+            }
+        }
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+
+    fn internal_set_payment_status<T: PayoutInfo>(
+        &mut self,
+        id: u64,
+        index: u64,
+        status: PaymentStatus,
+    ) {
+        let mut payout = T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND);
+        if let PaymentStatus::Failed { reason } = &status {
+            env::log_str(&format!(
+                "Payment {} of {:?} payout {} failed: {}",
+                index,
+                T::KIND,
+                id,
+                reason
+            ));
+        }
+        let payment = &mut payout.payments[index as usize];
+        payment.status = status;
+        payment.transfer_issued = false;
+        T::payouts_mut(self).insert(&id, &payout);
+    }
+
+    fn internal_get_payments<T: PayoutInfo>(&self, id: u64) -> Vec<PaymentRecord> {
+        T::payouts(self).get(&id).expect(T::ERR_NOT_FOUND).payments
+    }
+
+    fn internal_get_failed_payments<T: PayoutInfo>(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<FailedPayment> {
+        (from_index..=std::cmp::min(from_index + limit, T::last_id(self)))
+            .filter_map(|id| T::payouts(self).get(&id).map(|p| (id, p)))
+            .flat_map(|(id, payout)| {
+                payout
+                    .payments
+                    .into_iter()
+                    .enumerate()
+                    .filter(|(_, payment)| matches!(payment.status, PaymentStatus::Failed { .. }))
+                    .map(move |(index, payment)| FailedPayment {
+                        id,
+                        index: index as u64,
+                        payment,
+                    })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, get_logs, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::oracle::OracleProvider;
+    use crate::payout::fixtures::{contract_with_proposal, open_proposal};
+
+    fn context(block_height: u64) -> VMContextBuilder {
+        let mut builder = VMContextBuilder::new();
+        builder
+            .signer_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .block_index(block_height);
+        builder
+    }
+
+    fn payment(status: PaymentStatus, transfer_issued: bool) -> PaymentRecord {
+        PaymentRecord {
+            account_id: accounts(1),
+            amount: USD::from_dollars(10),
+            asset: PayoutAsset::Near,
+            status,
+            scheduled_at: 1,
+            transfer_issued,
+        }
+    }
+
+    /// a contract with an approved proposal whose only payment has the given status
+    fn contract_with_payment(status: PaymentStatus) -> Contract {
+        let mut payout = open_proposal();
+        payout.status = PayoutStatus::Approved;
+        payout.payments = vec![payment(status, false)];
+        contract_with_proposal(payout)
+    }
+
+    #[test]
+    fn issued_pending_payment_is_never_retried() {
+        testing_env!(context(1 + PAYMENT_TIMEOUT_BLOCKS * 10).build());
+        assert!(!payment(PaymentStatus::Pending, true).is_retryable());
+        assert!(payment(PaymentStatus::Pending, false).is_retryable());
+    }
+
+    #[test]
+    fn failed_payment_is_rescheduled_on_retry() {
+        testing_env!(context(10).build());
+        let mut contract = contract_with_payment(PaymentStatus::Failed {
+            reason: "transfer failed".to_string(),
+        });
+        contract.retry_payment(PayoutKind::Proposal, 1);
+        let payments = contract.get_payments(PayoutKind::Proposal, 1);
+        assert_eq!(payments[0].status, PaymentStatus::Pending);
+        assert_eq!(payments[0].scheduled_at, 10);
+        assert!(!payments[0].is_retryable());
+    }
+
+    #[test]
+    #[should_panic(expected = "no payment to retry")]
+    fn failed_payment_is_retried_only_once() {
+        testing_env!(context(10).build());
+        let mut contract = contract_with_payment(PaymentStatus::Failed {
+            reason: "transfer failed".to_string(),
+        });
+        contract.retry_payment(PayoutKind::Proposal, 1);
+        contract.retry_payment(PayoutKind::Proposal, 1);
+    }
+
+    #[test]
+    #[should_panic(expected = "no payment to retry")]
+    fn paid_payment_is_never_retried() {
+        testing_env!(context(10 + PAYMENT_TIMEOUT_BLOCKS * 10).build());
+        let mut contract = contract_with_payment(PaymentStatus::Paid {
+            amount_yocto: U128(1),
+            rate: USD::from_dollars(1),
+            block: 2,
+        });
+        contract.retry_payment(PayoutKind::Proposal, 1);
+    }
+
+    #[test]
+    fn malformed_rate_fails_the_payments() {
+        testing_env!(context(10).build());
+        let mut contract = contract_with_payment(PaymentStatus::Pending);
+        testing_env!(
+            context(10).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Successful(b"{\"rate\":".to_vec())],
+        );
+        contract.make_transfers(PayoutKind::Proposal, 1, 1, 0);
+        let payments = contract.get_payments(PayoutKind::Proposal, 1);
+        assert_eq!(
+            payments[0].status,
+            PaymentStatus::Failed {
+                reason: "malformed exchange rate".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn failed_oracle_falls_back_to_the_next_one() {
+        testing_env!(context(10).build());
+        let mut contract = contract_with_payment(PaymentStatus::Pending);
+        let fallback = OracleProvider {
+            account_id: accounts(2),
+            ..contract.oracle.providers[0].clone()
+        };
+        contract.oracle.providers.push(fallback);
+        testing_env!(
+            context(10).build(),
+            near_sdk::VMConfig::test(),
+            near_sdk::RuntimeFeesConfig::test(),
+            HashMap::default(),
+            vec![PromiseResult::Failed],
+        );
+        // the payment waits for the rate of the fallback oracle
+        contract.make_transfers(PayoutKind::Proposal, 1, 1, 0);
+        let payments = contract.get_payments(PayoutKind::Proposal, 1);
+        assert_eq!(payments[0].status, PaymentStatus::Pending);
+        assert!(get_logs()[0].ends_with("trying the next oracle"));
+        // and fails once no oracle is left
+        contract.make_transfers(PayoutKind::Proposal, 1, 1, 1);
+        let payments = contract.get_payments(PayoutKind::Proposal, 1);
+        assert_eq!(
+            payments[0].status,
+            PaymentStatus::Failed {
+                reason: "exchange rate unavailable".to_string()
+            }
+        );
+    }
+}
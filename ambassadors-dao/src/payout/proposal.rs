@@ -1,9 +1,10 @@
-use near_sdk::{env, near_bindgen};
+use near_sdk::near_bindgen;
 
 use super::{
     types::{Action, USD},
     *,
 };
+use crate::amounts::{assert_valid_prize_table, Prize};
 
 pub type ProposalPayout = Payout<Proposal>;
 
@@ -14,110 +15,432 @@ pub enum Proposal {
     Hackathon {
         /// number of expected registrations in the hackathon
         expected_registrations: u64,
-        /// estimated budget required for the hackathon in near tokens
+        /// estimated budget required for the hackathon in USD
         estimated_budget: USD,
         /// s3 link to a PDF with details of the proposal
         supporting_document: ResourceLink,
+        /// the prizes paid to the winners, in the order of the winners,
+        /// the prizes of the reward schedule are used by default
+        prize_table: Option<Vec<Prize>>,
+        /// the milestones the budget is released in, the whole budget is paid
+        /// on approval by default
+        milestones: Option<Vec<Milestone>>,
     },
     MemeContest {
         /// number of expected registrations in the meme contest
         expected_registrations: u64,
-        /// estimated budget required for the meme contest in near tokens
+        /// estimated budget required for the meme contest in USD
         estimated_budget: USD,
         /// s3 link to a PDF with details of the proposal
         supporting_document: ResourceLink,
+        /// the prizes paid to the winners, in the order of the winners,
+        /// the prizes of the reward schedule are used by default
+        prize_table: Option<Vec<Prize>>,
+        /// the milestones the budget is released in, the whole budget is paid
+        /// on approval by default
+        milestones: Option<Vec<Milestone>>,
     },
     Open {
-        /// estimated budget required for the proposal in near tokens
+        /// estimated budget required for the proposal in USD
         estimated_budget: USD,
         /// s3 link to a PDF with details of the proposal
         supporting_document: ResourceLink,
+        /// the milestones the budget is released in, the whole budget is paid
+        /// on approval by default
+        milestones: Option<Vec<Milestone>>,
+    },
+    /// A report on the next milestone of an approved proposal of the same
+    /// proposer, the tranche of the milestone is paid once it is approved
+    MilestoneReport {
+        /// the proposal the milestone belongs to
+        proposal_id: u64,
+        /// links to the deliverables of the milestone
+        report_links: Vec<ResourceLink>,
     },
 }
 
-impl From<PayoutInput<Proposal>> for Payout<Proposal> {
-    fn from(input: PayoutInput<Proposal>) -> Self {
-        Self {
-            proposer: env::predecessor_account_id(),
-            description: input.description,
-            info: input.information,
-            status: PayoutStatus::UnderConsideration,
-            votes_count: VotesCount::new(),
-            votes: HashMap::default(),
-        }
+/// A milestone of a proposal and the part of its budget released with it
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Milestone {
+    /// the percentage of the budget released with the milestone
+    pub percentage: u64,
+    /// what is delivered at the milestone
+    pub deliverable: String,
+}
+
+/// The part of the budget of an approved proposal released with a milestone
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Tranche {
+    /// what is delivered at the milestone
+    pub deliverable: String,
+    /// the amount released with the milestone
+    pub amount: USD,
+    /// the last report submitted for the milestone, if any
+    pub report_id: Option<u64>,
+    /// if the amount was paid
+    pub released: bool,
+}
+
+/// Check the milestones are described and their percentages add up to 100.
+fn assert_valid_milestones(milestones: &[Milestone]) {
+    let total: u64 = milestones.iter().map(|m| m.percentage).sum();
+    let is_valid = milestones
+        .iter()
+        .all(|m| m.percentage > 0 && !m.deliverable.trim().is_empty());
+    if milestones.is_empty() || !is_valid || total != 100 {
+        panic!("{}", error::ERR_INVALID_MILESTONES);
     }
 }
 
-#[near_bindgen]
-impl Contract {
-    /// create a proposal payout
-    pub fn add_payout_proposal(&mut self, payout: PayoutInput<Proposal>) -> u64 {
-        // validate input
-        match &payout.information {
+/// Split a budget into the tranches of the milestones, the last tranche gets
+/// what is left after rounding down the others.
+fn split_budget(budget: USD, milestones: &[Milestone]) -> Vec<Tranche> {
+    let mut remaining = budget.0;
+    milestones
+        .iter()
+        .enumerate()
+        .map(|(index, milestone)| {
+            let amount = if index + 1 == milestones.len() {
+                remaining
+            } else {
+                (budget.0 as u128 * milestone.percentage as u128 / 100) as u64
+            };
+            remaining -= amount;
+            Tranche {
+                deliverable: milestone.deliverable.clone(),
+                amount: USD(amount),
+                report_id: None,
+                released: false,
+            }
+        })
+        .collect()
+}
+
+impl PayoutInfo for Proposal {
+    const KIND: PayoutKind = PayoutKind::Proposal;
+    const ERR_NOT_FOUND: &'static str = error::ERR_PROPOSAL_NOT_FOUND;
+
+    fn payouts(contract: &Contract) -> &LookupMap<u64, Payout<Self>> {
+        &contract.proposals
+    }
+
+    fn payouts_mut(contract: &mut Contract) -> &mut LookupMap<u64, Payout<Self>> {
+        &mut contract.proposals
+    }
+
+    fn last_id(contract: &Contract) -> u64 {
+        contract.last_proposal_id
+    }
+
+    fn validate(contract: &mut Contract, id: u64, info: &Self) {
+        match info {
             Proposal::Hackathon {
+                estimated_budget,
                 supporting_document,
+                prize_table,
+                milestones,
                 ..
-            } => {
-                validation::assert_valid_resource_url(supporting_document);
             }
-            Proposal::MemeContest {
+            | Proposal::MemeContest {
+                estimated_budget,
                 supporting_document,
+                prize_table,
+                milestones,
                 ..
             } => {
                 validation::assert_valid_resource_url(supporting_document);
+                if let Some(prizes) = prize_table {
+                    // the prizes and the completion amount of the organizer are
+                    // paid out of the budget of the event
+                    let amounts =
+                        contract.internal_reward_schedule(contract.reward_schedule_version);
+                    let completion = match info {
+                        Proposal::Hackathon { .. } => amounts.hackathon_completion,
+                        _ => amounts.meme_contest_completion,
+                    };
+                    if prizes.is_empty() {
+                        panic!("{}", error::ERR_INVALID_PRIZE_TABLE);
+                    }
+                    let total = assert_valid_prize_table(prizes)
+                        .checked_add(completion)
+                        .expect(error::ERR_AMOUNT_OVERFLOW);
+                    if total > *estimated_budget {
+                        panic!("{}", error::ERR_INVALID_PRIZE_TABLE);
+                    }
+                }
+                if let Some(milestones) = milestones {
+                    assert_valid_milestones(milestones);
+                }
             }
             Proposal::Open {
                 supporting_document,
+                milestones,
                 ..
             } => {
                 validation::assert_valid_resource_url(supporting_document);
+                if let Some(milestones) = milestones {
+                    assert_valid_milestones(milestones);
+                }
+            }
+            Proposal::MilestoneReport {
+                proposal_id,
+                report_links,
+            } => {
+                if report_links.is_empty() {
+                    panic!("ERR_REPORT_LINKS_CANNOT_BE_EMPTY")
+                }
+                for link in report_links {
+                    validation::assert_valid_resource_url(link);
+                }
+                contract.internal_report_milestone(*proposal_id, id);
+            }
+        }
+    }
+
+    fn unlink(contract: &mut Contract, id: u64, info: &Self) {
+        if let Proposal::MilestoneReport { proposal_id, .. } = info {
+            if let Some(mut tranches) = contract.tranches.get(proposal_id) {
+                for tranche in tranches.iter_mut() {
+                    if tranche.report_id == Some(id) {
+                        tranche.report_id = None;
+                    }
+                }
+                contract.tranches.insert(proposal_id, &tranches);
+            }
+        }
+    }
+
+    fn approved_transfers(
+        contract: &Contract,
+        id: u64,
+        payout: &Payout<Self>,
+    ) -> Result<Vec<(AccountId, USD)>, String> {
+        // here the amount is in USD
+        let (estimated_budget, milestones) = match &payout.info {
+            Proposal::Hackathon {
+                estimated_budget,
+                milestones,
+                ..
+            }
+            | Proposal::MemeContest {
+                estimated_budget,
+                milestones,
+                ..
+            }
+            | Proposal::Open {
+                estimated_budget,
+                milestones,
+                ..
+            } => (*estimated_budget, milestones),
+            Proposal::MilestoneReport { proposal_id, .. } => {
+                let tranche = contract.internal_reported_tranche(*proposal_id, id);
+                return Ok(vec![(payout.proposer.clone(), tranche.amount)]);
             }
         };
+        let budget = contract.internal_organizer_budget(payout, estimated_budget);
+        let transfer_amount = match milestones {
+            // only the first tranche is released on approval
+            Some(milestones) => split_budget(budget, milestones)[0].amount,
+            None => budget,
+        };
+        // the whole budget is held in escrow for the completion
+        if transfer_amount.0 == 0 {
+            return Ok(Vec::new());
+        }
+        Ok(vec![(payout.proposer.clone(), transfer_amount)])
+    }
 
-        // anyone can create this, no permission checks needed
+    fn on_approved(
+        contract: &mut Contract,
+        id: u64,
+        payout: &Payout<Self>,
+        _transfers: &[(AccountId, USD)],
+    ) {
+        match &payout.info {
+            Proposal::Hackathon {
+                estimated_budget,
+                milestones: Some(milestones),
+                ..
+            }
+            | Proposal::MemeContest {
+                estimated_budget,
+                milestones: Some(milestones),
+                ..
+            }
+            | Proposal::Open {
+                estimated_budget,
+                milestones: Some(milestones),
+                ..
+            } => {
+                let budget = contract.internal_organizer_budget(payout, *estimated_budget);
+                let mut tranches = split_budget(budget, milestones);
+                tranches[0].released = true;
+                contract.tranches.insert(&id, &tranches);
+            }
+            Proposal::MilestoneReport { proposal_id, .. } => {
+                contract.internal_release_tranche(*proposal_id, id);
+            }
+            _ => {}
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// create a proposal payout
+    #[payable]
+    pub fn add_payout_proposal(&mut self, payout: PayoutInput<Proposal>) -> u64 {
+        self.internal_assert_can_add_payout(&PayoutKind::Proposal);
+        let bond = self.internal_take_bond();
+        // validate input
+        Proposal::validate(self, self.last_proposal_id + 1, &payout.information);
 
         // add the proposal to Contract.proposals
         let new_id = self.last_proposal_id + 1;
-        self.proposals.insert(&new_id, &Payout::from(payout));
+        self.proposals.insert(
+            &new_id,
+            &Payout::new(
+                payout,
+                self.reward_schedule_version,
+                self.council_version,
+                bond,
+            ),
+        );
         self.last_proposal_id = new_id;
         new_id
     }
 
     /// act on a proposal payout
     pub fn act_payout_proposal(&mut self, id: u64, action: Action, note: Option<String>) {
-        // check if proposal with id exists
-        let mut proposal = match self.proposals.get(&id) {
-            Some(p) => p,
-            None => panic!("{}", error::ERR_PROPOSAL_NOT_FOUND),
-        };
-        internal_act_payout(
-            self.members.is_council_member(&env::signer_account_id()),
-            self.members.get_council_size() as u64,
-            &mut proposal,
-            action,
-            note,
-        );
-        self.proposals.insert(&id, &proposal);
-        // check if payout state is approved
-        if proposal.status == PayoutStatus::Approved {
-            // here tokens is in near value
-            let transfer_amount = match proposal.info {
-                Proposal::Hackathon {
-                    estimated_budget, ..
-                } => estimated_budget,
-                Proposal::MemeContest {
-                    estimated_budget, ..
-                } => estimated_budget,
-                Proposal::Open {
-                    estimated_budget, ..
-                } => estimated_budget,
-            };
-            self.get_exchange_rate().then(ext::make_transfers(
-                vec![(proposal.proposer, transfer_amount)],
-                env::current_account_id(),
-                0,
-                Gas(types::ONE_TGAS),
-            ));
+        self.internal_act_on_payout::<Proposal>(id, action, note);
+    }
+
+    /// Returns the tranches of an approved proposal with milestones.
+    pub fn get_tranches(&self, proposal_id: u64) -> Vec<Tranche> {
+        self.tranches.get(&proposal_id).unwrap_or_default()
+    }
+}
+
+impl Contract {
+    /// Record a report on the next milestone of a proposal. Panics if the
+    /// signer is not the proposer, if every tranche was released, or if the
+    /// milestone already has a report that can still be approved.
+    fn internal_report_milestone(&mut self, proposal_id: u64, report_id: u64) {
+        let proposal = self
+            .proposals
+            .get(&proposal_id)
+            .expect(error::ERR_PROPOSAL_NOT_FOUND);
+        if proposal.proposer != env::predecessor_account_id() {
+            panic!(
+                "{}: {}",
+                error::ERR_NOT_PERMITTED,
+                "only the proposer can report on its milestones"
+            )
+        }
+        let mut tranches = self
+            .tranches
+            .get(&proposal_id)
+            .expect(error::ERR_MILESTONE_NOT_FOUND);
+        let rule = self.vote_policy.rule_for(&PayoutKind::Proposal);
+        let tranche = tranches
+            .iter_mut()
+            .find(|tranche| !tranche.released)
+            .expect(error::ERR_MILESTONE_NOT_FOUND);
+        if let Some(previous_id) = tranche.report_id {
+            let previous = self
+                .proposals
+                .get(&previous_id)
+                .expect(error::ERR_PROPOSAL_NOT_FOUND);
+            if previous.is_pending(rule) {
+                panic!("{}", error::ERR_MILESTONE_ALREADY_REPORTED)
+            }
         }
+        tranche.report_id = Some(report_id);
+        self.tranches.insert(&proposal_id, &tranches);
+    }
+
+    /// the part of the budget of a proposal paid to its proposer, on approval
+    /// or in tranches, the rest is held in escrow for its completion bounty
+    fn internal_organizer_budget(&self, proposal: &ProposalPayout, estimated_budget: USD) -> USD {
+        USD(estimated_budget.0 - self.internal_completion_escrow(proposal).0)
+    }
+
+    /// the unreleased tranche of a proposal reported by a milestone report
+    fn internal_reported_tranche(&self, proposal_id: u64, report_id: u64) -> Tranche {
+        self.tranches
+            .get(&proposal_id)
+            .expect(error::ERR_MILESTONE_NOT_FOUND)
+            .into_iter()
+            .find(|tranche| tranche.report_id == Some(report_id) && !tranche.released)
+            .expect(error::ERR_MILESTONE_NOT_FOUND)
+    }
+
+    /// release the tranche of the milestone of an approved report
+    fn internal_release_tranche(&mut self, proposal_id: u64, report_id: u64) {
+        let mut tranches = self
+            .tranches
+            .get(&proposal_id)
+            .expect(error::ERR_MILESTONE_NOT_FOUND);
+        let tranche = tranches
+            .iter_mut()
+            .find(|tranche| tranche.report_id == Some(report_id) && !tranche.released)
+            .expect(error::ERR_MILESTONE_NOT_FOUND);
+        tranche.released = true;
+        self.tranches.insert(&proposal_id, &tranches);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn milestone(percentage: u64) -> Milestone {
+        Milestone {
+            percentage,
+            deliverable: "venue booked".to_string(),
+        }
+    }
+
+    #[test]
+    fn splits_budget_into_tranches() {
+        let tranches = split_budget(
+            USD(1_000_001),
+            &[milestone(30), milestone(30), milestone(40)],
+        );
+        let amounts: Vec<u64> = tranches.iter().map(|t| t.amount.0).collect();
+        assert_eq!(amounts, vec![300_000, 300_000, 400_001]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_MILESTONES")]
+    fn rejects_milestones_not_adding_up() {
+        assert_valid_milestones(&[milestone(50), milestone(40)]);
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_PRIZE_TABLE")]
+    fn prizes_and_completion_must_fit_the_budget() {
+        near_sdk::testing_env!(near_sdk::test_utils::VMContextBuilder::new().build());
+        let mut contract = Contract::new(
+            "dao".to_string(),
+            "testing".to_string(),
+            vec![near_sdk::test_utils::accounts(0)],
+            None,
+        );
+        // the prize fits the budget, but not with the completion amount of $40
+        let hackathon = Proposal::Hackathon {
+            expected_registrations: 100,
+            estimated_budget: USD::from_dollars(100),
+            supporting_document: "https://example.com/doc.pdf".to_string(),
+            prize_table: Some(vec![Prize {
+                category: None,
+                rank: 1,
+                amount: USD::from_dollars(100),
+            }]),
+            milestones: None,
+        };
+        Proposal::validate(&mut contract, 1, &hackathon);
     }
 }
@@ -0,0 +1,134 @@
+//! Recount of the payouts under consideration after the council changes
+
+use near_sdk::near_bindgen;
+
+use super::*;
+
+#[near_bindgen]
+impl Contract {
+    /// Recount the payouts of a kind under consideration that were last counted
+    /// with a previous council, starting at the given id and processing at most
+    /// `limit` ids. Returns the id to start the next call at.
+    /// Anyone can call this.
+    pub fn recount_payouts(&mut self, kind: PayoutKind, from_id: u64, limit: u64) -> u64 {
+        match kind {
+            PayoutKind::Proposal => self.internal_recount_payouts::<Proposal>(from_id, limit),
+            PayoutKind::Bounty => self.internal_recount_payouts::<Bounty>(from_id, limit),
+            PayoutKind::Miscellaneous => {
+                self.internal_recount_payouts::<Miscellaneous>(from_id, limit)
+            }
+            PayoutKind::Referral => self.internal_recount_payouts::<Referral>(from_id, limit),
+            PayoutKind::Governance => {
+                self.internal_recount_payouts::<GovernanceProposal>(from_id, limit)
+            }
+        }
+    }
+
+    /// Returns the version of the council, incremented whenever a member is
+    /// added or removed.
+    pub fn get_council_version(&self) -> u64 {
+        self.council_version
+    }
+}
+
+impl Contract {
+    fn internal_recount_payouts<T: PayoutInfo>(&mut self, from_id: u64, limit: u64) -> u64 {
+        let to_id = from_id
+            .max(1)
+            .saturating_add(limit)
+            .min(T::last_id(self) + 1);
+        for id in from_id.max(1)..to_id {
+            if let Some(mut payout) = T::payouts(self).get(&id) {
+                if self.internal_recount_payout(id, &mut payout) {
+                    T::payouts_mut(self).insert(&id, &payout);
+                }
+            }
+        }
+        to_id
+    }
+
+    /// Drop the votes that their voters can no longer cast and the vetoes of
+    /// removed council members, then expire the payout if its voting period is
+    /// over or finalize it if the remaining votes decide the outcome with the
+    /// current council.
+    /// Returns if the payout was recounted, it must then be stored.
+    pub(crate) fn internal_recount_payout<T: PayoutInfo>(
+        &mut self,
+        id: u64,
+        payout: &mut Payout<T>,
+    ) -> bool {
+        if payout.status != PayoutStatus::UnderConsideration
+            || payout.council_version == self.council_version
+        {
+            return false;
+        }
+        let dropped: Vec<AccountId> = payout
+            .votes
+            .iter()
+            .filter(|(voter, record)| {
+                !self
+                    .policy
+                    .is_allowed(&self.members, voter, &T::KIND, &record.vote.to_action())
+            })
+            .map(|(voter, _)| voter.clone())
+            .collect();
+        for voter in dropped {
+            payout.remove_vote(&voter);
+        }
+        let members = &self.members;
+        payout
+            .vetoes
+            .retain(|account_id, _| members.is_council_member(account_id));
+        payout.council_version = self.council_version;
+
+        let rule = self.vote_policy.rule_for(&T::KIND);
+        if payout.is_expired(rule) {
+            payout.status = PayoutStatus::Expired;
+        } else {
+            internal_update_payout_status(self.members.get_council_size() as u64, rule, payout);
+        }
+        if payout.status == PayoutStatus::Approved {
+            self.internal_execute_payout(id, payout);
+        }
+        payout.record_appeal_outcome();
+        internal_refund_bond(payout);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::test_utils::{accounts, VMContextBuilder};
+    use near_sdk::testing_env;
+
+    use super::*;
+    use crate::members::Members;
+    use crate::payout::fixtures::{contract_with_proposal, open_proposal};
+    use crate::policy::DEFAULT_VOTING_PERIOD;
+    use crate::types::Action;
+
+    #[test]
+    fn expired_payout_is_not_approved_by_the_recount() {
+        testing_env!(VMContextBuilder::new()
+            .signer_account_id(accounts(0))
+            .predecessor_account_id(accounts(0))
+            .build());
+        let mut contract = contract_with_proposal(open_proposal());
+        contract.members = Members::from_council(vec![accounts(0), accounts(1), accounts(2)]);
+        contract.act_payout_proposal(1, Action::VoteApprove, None);
+        assert_eq!(
+            contract.proposals.get(&1).unwrap().status,
+            PayoutStatus::UnderConsideration
+        );
+        // the approve vote alone would reach the quorum of the shrunk council
+        contract.members = Members::from_council(vec![accounts(0)]);
+        contract.council_version += 1;
+        testing_env!(VMContextBuilder::new()
+            .block_timestamp(DEFAULT_VOTING_PERIOD)
+            .build());
+        assert_eq!(contract.recount_payouts(PayoutKind::Proposal, 0, 10), 2);
+        let payout = contract.proposals.get(&1).unwrap();
+        assert_eq!(payout.status, PayoutStatus::Expired);
+        assert_eq!(payout.council_version, 1);
+    }
+}
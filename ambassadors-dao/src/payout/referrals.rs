@@ -43,33 +43,32 @@ pub enum Referral {
     },
 }
 
-impl From<PayoutInput<Referral>> for Payout<Referral> {
-    fn from(input: PayoutInput<Referral>) -> Self {
-        Self {
-            proposer: env::predecessor_account_id(),
-            description: input.description,
-            info: input.information,
-            status: PayoutStatus::UnderConsideration,
-            votes_count: VotesCount::new(),
-            votes: HashMap::default(),
-        }
+impl PayoutInfo for Referral {
+    const KIND: PayoutKind = PayoutKind::Referral;
+    const ERR_NOT_FOUND: &'static str = error::ERR_REFERRAL_NOT_FOUND;
+
+    fn payouts(contract: &Contract) -> &LookupMap<u64, Payout<Self>> {
+        &contract.referrals
     }
-}
 
-#[near_bindgen]
-impl Contract {
-    /// create a new referral payout
-    pub fn add_payout_referral(&mut self, payout: PayoutInput<Referral>) -> u64 {
-        // validate input
-        match &payout.information {
+    fn payouts_mut(contract: &mut Contract) -> &mut LookupMap<u64, Payout<Self>> {
+        &mut contract.referrals
+    }
+
+    fn last_id(contract: &Contract) -> u64 {
+        contract.last_referral_id
+    }
+
+    fn validate(contract: &mut Contract, _id: u64, info: &Self) {
+        match info {
             Referral::AmbassadorRegistration {
                 referred_id,
                 referrer_id,
             } => {
-                if !self.members.is_registered_ambassador(referred_id) {
+                if !contract.members.is_registered_ambassador(referred_id) {
                     panic!("{}", error::ERR_REFERRED_MEMBER_NOT_FOUND);
                 }
-                if self
+                if contract
                     .members
                     .ambassadors
                     .get(referrer_id)
@@ -84,29 +83,79 @@ impl Contract {
                 proof_link,
                 ..
             } => {
-                if self.members.is_registered_ambassador(referred_id) {
+                if contract.members.is_registered_ambassador(referred_id) {
                     panic!("{}", error::ERR_REFERRED_MEMBER_NOT_FOUND);
                 }
                 validation::assert_valid_resource_url(proof_link);
             }
             Referral::Recruitment { referred_id, .. } => {
-                if self.members.is_registered_ambassador(referred_id) {
+                if contract.members.is_registered_ambassador(referred_id) {
                     panic!("{}", error::ERR_REFERRED_MEMBER_NOT_FOUND);
                 }
             }
+        }
+    }
+
+    fn unlink(_contract: &mut Contract, _id: u64, _info: &Self) {}
+
+    fn approved_transfers(
+        contract: &Contract,
+        _id: u64,
+        payout: &Payout<Self>,
+    ) -> Result<Vec<(AccountId, USD)>, String> {
+        let amounts = contract.internal_reward_schedule(payout.reward_schedule_version);
+        // here the amount is in USD
+        let transfer = match &payout.info {
+            Referral::AmbassadorRegistration { referred_id, .. } => {
+                (referred_id.clone(), amounts.ca_register_referral)
+            }
+            Referral::Recruitment { referred_id, .. } => {
+                (referred_id.clone(), amounts.recruitment_referral)
+            }
+            Referral::NearCertifiedDeveloper {
+                referred_id, kind, ..
+            } => (
+                referred_id.clone(),
+                match kind {
+                    NCDReferralKind::Completion => amounts.ncd_completion_referral,
+                    NCDReferralKind::FormFilled => amounts.ncd_form_filled_referral,
+                },
+            ),
         };
+        Ok(vec![transfer])
+    }
 
-        // anyone can create this, no permission checks needed
+    fn on_approved(
+        contract: &mut Contract,
+        _id: u64,
+        payout: &Payout<Self>,
+        _transfers: &[(AccountId, USD)],
+    ) {
+        if let Referral::AmbassadorRegistration { referrer_id, .. } = &payout.info {
+            contract
+                .members
+                .ambassadors
+                .get_mut(referrer_id)
+                .unwrap()
+                .registration_referral_used = true;
+        }
+    }
+}
 
-        // add the referral to Contract.referrals
-        let new_id = self.last_referral_id + 1;
-        self.referrals.insert(&new_id, &Payout::from(payout));
-        self.last_referral_id = new_id;
-        new_id
+#[near_bindgen]
+impl Contract {
+    /// create a new referral payout
+    #[payable]
+    pub fn add_payout_referral(&mut self, payout: PayoutInput<Referral>) -> u64 {
+        self.internal_assert_can_add_payout(&PayoutKind::Referral);
+        let bond = self.internal_take_bond();
+        self.internal_add_payout_referral(payout, bond)
     }
 
     // Add a registration referral using a referral token
+    #[payable]
     pub fn add_registration_referral_with_token(&mut self, token: String) -> u64 {
+        self.internal_assert_can_add_payout(&PayoutKind::Referral);
         let signer = env::signer_account_id();
         let ambassador: &mut members::AmbassadorProfile =
             match self.members.ambassadors.get_mut(&signer) {
@@ -127,13 +176,17 @@ impl Contract {
                     panic!("{}", error::ERR_NOT_PERMITTED);
                 } else {
                     ambassador.registration_referral_used = true;
-                    self.add_payout_referral(PayoutInput::<Referral> {
-                        description: "Ambassador registration referral".to_string(),
-                        information: Referral::AmbassadorRegistration {
-                            referrer_id: signer,
-                            referred_id: account_id,
+                    let bond = self.internal_take_bond();
+                    self.internal_add_payout_referral(
+                        PayoutInput::<Referral> {
+                            description: "Ambassador registration referral".to_string(),
+                            information: Referral::AmbassadorRegistration {
+                                referrer_id: signer,
+                                referred_id: account_id,
+                            },
                         },
-                    })
+                        bond,
+                    )
                 }
             }
             // invalid referral token
@@ -145,53 +198,33 @@ impl Contract {
 
     /// act on a referral payout
     pub fn act_payout_referral(&mut self, id: u64, action: Action, note: Option<String>) {
-        // check if proposal with id exists
-        let mut referral = match self.referrals.get(&id) {
-            Some(p) => p,
-            None => panic!("{}", error::ERR_PROPOSAL_NOT_FOUND),
-        };
-        internal_act_payout(
-            self.members.is_council_member(&env::signer_account_id()),
-            self.members.get_council_size() as u64,
-            &mut referral,
-            action,
-            note,
+        self.internal_act_on_payout::<Referral>(id, action, note);
+    }
+}
+
+impl Contract {
+    /// validate and add a referral payout without checking the permission of the
+    /// signer, used when the referral is created on their behalf
+    pub(crate) fn internal_add_payout_referral(
+        &mut self,
+        payout: PayoutInput<Referral>,
+        bond: Balance,
+    ) -> u64 {
+        // validate input
+        Referral::validate(self, self.last_referral_id + 1, &payout.information);
+
+        // add the referral to Contract.referrals
+        let new_id = self.last_referral_id + 1;
+        self.referrals.insert(
+            &new_id,
+            &Payout::new(
+                payout,
+                self.reward_schedule_version,
+                self.council_version,
+                bond,
+            ),
         );
-        self.referrals.insert(&id, &referral);
-        // check if payout state is approved
-        if referral.status == PayoutStatus::Approved {
-            // here tokens is in near value
-            let transfer = match referral.info {
-                Referral::AmbassadorRegistration {
-                    referred_id,
-                    referrer_id,
-                } => {
-                    self.members
-                        .ambassadors
-                        .get_mut(&referrer_id)
-                        .unwrap()
-                        .registration_referral_used = true;
-                    (referred_id, amounts::CA_REGISTER_REFERRAL_AMOUNT)
-                }
-                Referral::Recruitment { referred_id, .. } => {
-                    (referred_id, amounts::RECRUITMENT_REFERRAL_AMOUNT)
-                }
-                Referral::NearCertifiedDeveloper {
-                    referred_id, kind, ..
-                } => (
-                    referred_id,
-                    match kind {
-                        NCDReferralKind::Completion => amounts::NCD_COMPLETION_REFERRAL_AMOUNT,
-                        NCDReferralKind::FormFilled => amounts::NCD_FORM_FILLED_REFERRAL_AMOUNT,
-                    },
-                ),
-            };
-            self.get_exchange_rate().then(ext::make_transfers(
-                vec![transfer],
-                env::current_account_id(),
-                0,
-                Gas(types::ONE_TGAS),
-            ));
-        }
+        self.last_referral_id = new_id;
+        new_id
     }
 }
@@ -0,0 +1,430 @@
+//! Contains the voting policy of the DAO and the roles of its members
+
+use std::collections::{HashMap, HashSet};
+
+use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
+use near_sdk::json_types::U64;
+use near_sdk::serde::{Deserialize, Serialize};
+
+use crate::members::Members;
+use crate::payout::{PayoutKind, PayoutStatus, VotesCount};
+use crate::types::Action;
+use crate::*;
+
+/// One week in nanoseconds
+pub const DEFAULT_VOTING_PERIOD: u64 = 7 * 24 * 60 * 60 * 1_000_000_000;
+
+/// A rule that decides when the votes on a payout are enough to finalize it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteRule {
+    /// minimum number of votes that must be cast before a payout is finalized,
+    /// capped at the size of the council
+    pub quorum: u64,
+    /// ratio (numerator, denominator) of the approve and reject votes that
+    /// must be approve votes for a payout to be approved, abstentions are
+    /// not counted
+    pub threshold: (u64, u64),
+    /// finalize a payout as soon as the remaining votes can no longer
+    /// change the outcome
+    pub early_finalization: bool,
+    /// time in nanoseconds after submission during which a payout can be voted on
+    pub voting_period: U64,
+    /// ratio (numerator, denominator) of the council that must veto a payout
+    /// for it to be vetoed, when none of them is allowed to veto on their own
+    pub veto_threshold: (u64, u64),
+    /// keep the votes cast on a payout when its proposer amends it, they are
+    /// reset otherwise
+    pub keep_votes_on_amendment: bool,
+}
+
+impl VoteRule {
+    /// check the rule is well formed
+    pub fn assert_valid(&self) {
+        let (num, den) = self.threshold;
+        if self.quorum == 0 || den == 0 || num > den || self.voting_period.0 == 0 {
+            panic!("{}", error::ERR_INVALID_VOTE_POLICY);
+        }
+        let (num, den) = self.veto_threshold;
+        if num == 0 || den == 0 || num > den {
+            panic!("{}", error::ERR_INVALID_VOTE_POLICY);
+        }
+    }
+
+    /// if the given number of vetoes from the council meets the veto threshold
+    pub fn is_vetoed(&self, veto_count: u64, council_size: u64) -> bool {
+        let (num, den) = self.veto_threshold;
+        veto_count > 0 && veto_count * den >= num * council_size
+    }
+
+    /// if the given number of approve and reject votes meets the approval threshold
+    fn is_approved(&self, approve_count: u64, reject_count: u64) -> bool {
+        let (num, den) = self.threshold;
+        approve_count > 0 && approve_count * den >= num * (approve_count + reject_count)
+    }
+
+    /// Returns the final status of a payout with the given votes, or `None`
+    /// if the payout cannot be finalized yet.
+    pub fn evaluate(&self, council_size: u64, votes: &VotesCount) -> Option<PayoutStatus> {
+        let approve_count = votes.approve_count;
+        let reject_count = votes.reject_count;
+        let votes_cast = votes.total();
+        let quorum = self.quorum.min(council_size);
+
+        // the quorum has been reached, the votes cast decide the outcome
+        if votes_cast >= quorum {
+            return if self.is_approved(approve_count, reject_count) {
+                Some(PayoutStatus::Approved)
+            } else {
+                Some(PayoutStatus::Rejected)
+            };
+        }
+        if !self.early_finalization {
+            return None;
+        }
+        // the payout will be finalized once the quorum is reached, so the
+        // outcome is decided if the remaining votes cannot change it
+        let remaining = quorum - votes_cast;
+        if self.is_approved(approve_count, reject_count + remaining) {
+            Some(PayoutStatus::Approved)
+        } else if !self.is_approved(approve_count + remaining, reject_count) {
+            Some(PayoutStatus::Rejected)
+        } else {
+            None
+        }
+    }
+}
+
+/// The voting policy of the DAO, with optional overrides per kind of payout.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VotePolicy {
+    /// the rule applied to every payout kind without an override
+    pub default_rule: VoteRule,
+    /// rules for specific payout kinds
+    pub overrides: HashMap<PayoutKind, VoteRule>,
+}
+
+impl VotePolicy {
+    /// check every rule of the policy is well formed
+    pub fn assert_valid(&self) {
+        self.default_rule.assert_valid();
+        self.overrides.values().for_each(VoteRule::assert_valid);
+    }
+
+    /// get the rule that applies to a payout kind
+    pub fn rule_for(&self, kind: &PayoutKind) -> &VoteRule {
+        self.overrides.get(kind).unwrap_or(&self.default_rule)
+    }
+}
+
+impl Default for VotePolicy {
+    /// All council members must vote within a week and at least half of them must approve,
+    /// two thirds of the council can veto a payout.
+    fn default() -> Self {
+        Self {
+            default_rule: VoteRule {
+                quorum: u64::MAX,
+                threshold: (1, 2),
+                early_finalization: false,
+                voting_period: U64(DEFAULT_VOTING_PERIOD),
+                veto_threshold: (2, 3),
+                keep_votes_on_amendment: false,
+            },
+            overrides: HashMap::new(),
+        }
+    }
+}
+
+/// The accounts a role is given to
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub enum RoleKind {
+    /// every account
+    Everyone,
+    /// the members of the council
+    Council,
+    /// the registered ambassadors
+    Ambassador,
+    /// the given accounts
+    Group(HashSet<AccountId>),
+}
+
+impl RoleKind {
+    /// if the account is given the role
+    pub fn matches(&self, members: &Members, account_id: &AccountId) -> bool {
+        match self {
+            RoleKind::Everyone => true,
+            RoleKind::Council => members.is_council_member(account_id),
+            RoleKind::Ambassador => members.is_registered_ambassador(account_id),
+            RoleKind::Group(accounts) => accounts.contains(account_id),
+        }
+    }
+
+    /// if the role cannot be given to any account
+    fn is_empty(&self) -> bool {
+        matches!(self, RoleKind::Group(accounts) if accounts.is_empty())
+    }
+}
+
+/// A named role and the actions it allows
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct RolePermission {
+    /// the name of the role
+    pub name: String,
+    /// the accounts the role is given to
+    pub kind: RoleKind,
+    /// the allowed actions as `<payout kind>:<action>` labels, such as
+    /// `referral:VoteApprove`, either part can be `*` to match anything
+    pub permissions: HashSet<String>,
+}
+
+impl RolePermission {
+    /// if the role allows the action on payouts of the given kind
+    pub fn allows(&self, kind: &PayoutKind, action: &Action) -> bool {
+        let kind = kind.to_policy_label();
+        let action = action.to_policy_label();
+        [
+            format!("{}:{}", kind, action),
+            format!("{}:*", kind),
+            format!("*:{}", action),
+            "*:*".to_string(),
+        ]
+        .iter()
+        .any(|label| self.permissions.contains(label))
+    }
+}
+
+/// The roles of the DAO, an account can take an action if any of its roles allows it.
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct Policy {
+    pub roles: Vec<RolePermission>,
+}
+
+impl Policy {
+    /// Check the roles have distinct names and well formed permissions, and
+    /// that governance proposals can still be added and approved, otherwise
+    /// the roles could never be changed again.
+    pub fn assert_valid(&self) {
+        let mut names = HashSet::new();
+        for role in self.roles.iter() {
+            if role.name.is_empty() || !names.insert(&role.name) {
+                panic!("{}", error::ERR_INVALID_POLICY);
+            }
+            for permission in role.permissions.iter() {
+                match permission.split_once(':') {
+                    Some((kind, action)) if !kind.is_empty() && !action.is_empty() => {}
+                    _ => panic!("{}", error::ERR_INVALID_POLICY),
+                }
+            }
+        }
+        let can_govern = |action: &Action| {
+            self.roles
+                .iter()
+                .any(|role| !role.kind.is_empty() && role.allows(&PayoutKind::Governance, action))
+        };
+        if !can_govern(&Action::AddPayout) || !can_govern(&Action::VoteApprove) {
+            panic!("{}", error::ERR_INVALID_POLICY);
+        }
+    }
+
+    /// if the account can take the action on payouts of the given kind
+    pub fn is_allowed(
+        &self,
+        members: &Members,
+        account_id: &AccountId,
+        kind: &PayoutKind,
+        action: &Action,
+    ) -> bool {
+        self.roles
+            .iter()
+            .filter(|role| role.kind.matches(members, account_id))
+            .any(|role| role.allows(kind, action))
+    }
+}
+
+/// build a set of permission labels
+fn permissions(labels: &[&str]) -> HashSet<String> {
+    labels.iter().map(|label| label.to_string()).collect()
+}
+
+impl Default for Policy {
+    /// The council votes, marks payouts as spam, creates every kind of payout
+    /// and publishes bounties on the board, the ambassadors
+    /// create every kind of payout but governance proposals, and anyone can
+    /// remove their own payouts. Only the veto group can veto a payout on its
+    /// own. The campus lead and reviewer roles have no permissions.
+    fn default() -> Self {
+        Self {
+            roles: vec![
+                RolePermission {
+                    name: "council".to_string(),
+                    kind: RoleKind::Council,
+                    permissions: permissions(&[
+                        "*:VoteApprove",
+                        "*:VoteReject",
+                        "*:VoteAbstain",
+                        "*:RetractVote",
+                        "*:AddPayout",
+                        "*:MarkSpam",
+                        "bounty:PublishBounty",
+                    ]),
+                },
+                RolePermission {
+                    name: "campus_lead".to_string(),
+                    kind: RoleKind::Group(HashSet::new()),
+                    permissions: HashSet::new(),
+                },
+                RolePermission {
+                    name: "reviewer".to_string(),
+                    kind: RoleKind::Group(HashSet::new()),
+                    permissions: HashSet::new(),
+                },
+                RolePermission {
+                    name: "veto".to_string(),
+                    kind: RoleKind::Group(HashSet::new()),
+                    permissions: permissions(&["*:Veto"]),
+                },
+                RolePermission {
+                    name: "ambassador".to_string(),
+                    kind: RoleKind::Ambassador,
+                    permissions: permissions(&[
+                        "proposal:AddPayout",
+                        "bounty:AddPayout",
+                        "miscellaneous:AddPayout",
+                        "referral:AddPayout",
+                    ]),
+                },
+                RolePermission {
+                    name: "everyone".to_string(),
+                    kind: RoleKind::Everyone,
+                    permissions: permissions(&["*:RemovePayout"]),
+                },
+            ],
+        }
+    }
+}
+
+#[near_bindgen]
+impl Contract {
+    /// Returns the voting policy of this contract.
+    pub fn get_vote_policy(&self) -> VotePolicy {
+        self.vote_policy.clone()
+    }
+
+    /// Returns the roles of this contract.
+    pub fn get_policy(&self) -> Policy {
+        self.policy.clone()
+    }
+}
+
+impl Contract {
+    /// if the signer can take the action on payouts of the given kind
+    pub(crate) fn internal_is_allowed(&self, kind: &PayoutKind, action: &Action) -> bool {
+        self.policy
+            .is_allowed(&self.members, &env::signer_account_id(), kind, action)
+    }
+
+    /// Panics if the signer cannot add payouts of the given kind, with
+    /// `ERR_NOT_A_MEMBER` if the signer is neither an ambassador nor on the council.
+    pub(crate) fn internal_assert_can_add_payout(&self, kind: &PayoutKind) {
+        if self.internal_is_allowed(kind, &Action::AddPayout) {
+            return;
+        }
+        let signer = env::signer_account_id();
+        if !self.members.is_council_member(&signer)
+            && !self.members.is_registered_ambassador(&signer)
+        {
+            panic!("{}", error::ERR_NOT_A_MEMBER);
+        }
+        panic!("{}", error::ERR_NOT_PERMITTED);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn votes(approve_count: u64, reject_count: u64) -> VotesCount {
+        VotesCount {
+            approve_count,
+            reject_count,
+            abstain_count: 0,
+        }
+    }
+
+    #[test]
+    fn default_policy_waits_for_whole_council() {
+        let rule = VotePolicy::default().default_rule;
+        assert_eq!(rule.evaluate(3, &votes(2, 0)), None);
+        assert_eq!(rule.evaluate(3, &votes(2, 1)), Some(PayoutStatus::Approved));
+        assert_eq!(rule.evaluate(4, &votes(2, 2)), Some(PayoutStatus::Approved));
+        assert_eq!(rule.evaluate(3, &votes(1, 2)), Some(PayoutStatus::Rejected));
+    }
+
+    #[test]
+    fn abstentions_count_towards_quorum_only() {
+        let rule = VotePolicy::default().default_rule;
+        let mut count = votes(1, 0);
+        count.abstain_count = 2;
+        assert_eq!(rule.evaluate(3, &count), Some(PayoutStatus::Approved));
+        let mut count = votes(0, 0);
+        count.abstain_count = 3;
+        assert_eq!(rule.evaluate(3, &count), Some(PayoutStatus::Rejected));
+    }
+
+    #[test]
+    fn default_roles() {
+        let policy = Policy::default();
+        let council: AccountId = "council.near".parse().unwrap();
+        let anyone: AccountId = "anyone.near".parse().unwrap();
+        let members = Members::from_council(vec![council.clone()]);
+        let kind = PayoutKind::Referral;
+        assert!(policy.is_allowed(&members, &council, &kind, &Action::VoteApprove));
+        assert!(!policy.is_allowed(&members, &anyone, &kind, &Action::VoteApprove));
+        assert!(!policy.is_allowed(&members, &anyone, &kind, &Action::AddPayout));
+        assert!(policy.is_allowed(&members, &anyone, &kind, &Action::RemovePayout));
+        assert!(policy.is_allowed(&members, &council, &kind, &Action::MarkSpam));
+        let kind = PayoutKind::Bounty;
+        assert!(policy.is_allowed(&members, &council, &kind, &Action::PublishBounty));
+        assert!(!policy.is_allowed(&members, &anyone, &kind, &Action::PublishBounty));
+        let kind = PayoutKind::Governance;
+        assert!(policy.is_allowed(&members, &council, &kind, &Action::AddPayout));
+        assert!(!policy.is_allowed(&members, &anyone, &kind, &Action::AddPayout));
+    }
+
+    #[test]
+    fn quorum_and_early_finalization() {
+        let rule = VoteRule {
+            quorum: 3,
+            threshold: (2, 3),
+            early_finalization: true,
+            voting_period: U64(DEFAULT_VOTING_PERIOD),
+            veto_threshold: (2, 3),
+            keep_votes_on_amendment: false,
+        };
+        // quorum reached with 5 members
+        assert_eq!(rule.evaluate(5, &votes(2, 1)), Some(PayoutStatus::Approved));
+        assert_eq!(rule.evaluate(5, &votes(1, 2)), Some(PayoutStatus::Rejected));
+        // two rejections before quorum can never reach 2/3
+        assert_eq!(rule.evaluate(5, &votes(0, 2)), Some(PayoutStatus::Rejected));
+        assert_eq!(rule.evaluate(5, &votes(1, 1)), None);
+        // two approvals before quorum out of three is 2/3 whatever the last vote is
+        assert_eq!(rule.evaluate(5, &votes(2, 0)), Some(PayoutStatus::Approved));
+    }
+
+    #[test]
+    #[should_panic(expected = "ERR_INVALID_POLICY")]
+    fn roles_must_allow_governance() {
+        let mut policy = Policy::default();
+        policy.assert_valid();
+        // only the empty veto group could still approve governance proposals
+        policy.roles[0].permissions.remove("*:VoteApprove");
+        policy.roles[3]
+            .permissions
+            .insert("*:VoteApprove".to_string());
+        policy.assert_valid();
+    }
+}
@@ -1,22 +1,79 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
-use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::json_types::U128;
+use near_sdk::serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use near_sdk::{Balance, ONE_NEAR};
 
 pub const ONE_TGAS: u64 = 1_000_000_000_000;
 
+/// Default deposit required to submit a payout, covers about 10kb of storage
+pub const DEFAULT_SUBMISSION_BOND: Balance = ONE_NEAR / 10;
+
 pub type ReferralToken = String;
 
+/// Number of micro-dollars in a dollar
+pub const MICRO_USD_PER_USD: u64 = 1_000_000;
+
+/// An amount of US dollars with a fixed precision of a micro-dollar.
+/// It is serialized to JSON as a string of micro-dollars, "2500000" is $2.50
 #[allow(clippy::upper_case_acronyms)]
-pub type USD = f64;
+#[derive(
+    BorshSerialize, BorshDeserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Debug,
+)]
+pub struct USD(pub u64);
+
+impl USD {
+    /// a whole amount of dollars
+    pub const fn from_dollars(dollars: u64) -> Self {
+        Self(dollars * MICRO_USD_PER_USD)
+    }
+
+    /// converts a floating point amount of dollars, rounded to the nearest
+    /// micro-dollar, negative and NaN amounts are zero
+    pub fn from_f64(dollars: f64) -> Self {
+        Self((dollars * MICRO_USD_PER_USD as f64).round() as u64)
+    }
+
+    pub fn checked_add(self, other: Self) -> Option<Self> {
+        self.0.checked_add(other.0).map(Self)
+    }
+}
 
-pub(crate) fn usd_to_balance(amount: f64, rate: f64) -> Balance {
-    let mut near_tokens = amount / rate;
-    let mut divisor = 1_u128;
-    while near_tokens.fract() > 0.0001 {
-        near_tokens *= 10_f64;
-        divisor *= 10;
+impl std::fmt::Display for USD {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "${}.{:06}",
+            self.0 / MICRO_USD_PER_USD,
+            self.0 % MICRO_USD_PER_USD
+        )
     }
-    (near_tokens.trunc() as u128) * (ONE_NEAR / divisor)
+}
+
+impl Serialize for USD {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.serialize_str(&self.0.to_string())
+    }
+}
+
+impl<'de> Deserialize<'de> for USD {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let s: String = Deserialize::deserialize(deserializer)?;
+        s.parse::<u64>()
+            .map(Self)
+            .map_err(|err| de::Error::custom(err.to_string()))
+    }
+}
+
+/// Converts an amount of dollars to yoctonear with the rate of dollars per
+/// NEAR token, the result is rounded down to the yoctonear.
+pub(crate) fn usd_to_balance(amount: USD, rate: USD) -> Balance {
+    if rate.0 == 0 {
+        panic!("{}", crate::error::ERR_INVALID_EXCHANGE_RATE);
+    }
+    (amount.0 as u128)
+        .checked_mul(ONE_NEAR)
+        .expect(crate::error::ERR_AMOUNT_OVERFLOW)
+        / rate.0 as u128
 }
 
 /// Configuration of the DAO.
@@ -27,11 +84,27 @@ pub struct Config {
     pub name: String,
     /// purpose of DAO
     pub purpose: String,
+    /// the minimum deposit in yoctonear attached to the submission of a payout
+    pub submission_bond: U128,
 }
 
 impl Config {
     pub fn new(name: String, purpose: String) -> Self {
-        Self { name, purpose }
+        Self {
+            name,
+            purpose,
+            submission_bond: U128(DEFAULT_SUBMISSION_BOND),
+        }
+    }
+
+    /// check the name and purpose are not empty
+    pub fn assert_valid(&self) {
+        if self.name.is_empty() {
+            panic!("ERR_INVALID_NAME");
+        }
+        if self.purpose.is_empty() {
+            panic!("ERR_PURPOSE_EMPTY");
+        }
     }
 }
 
@@ -40,12 +113,26 @@ impl Config {
 #[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Debug)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Action {
+    /// add a payout, only used in the policy as a payout cannot be added
+    /// through an action on another one
+    AddPayout,
     /// remove a payout
     RemovePayout,
     /// approval vote for a payout
     VoteApprove,
     /// rejection vote for a payout
     VoteReject,
+    /// abstention vote for a payout
+    VoteAbstain,
+    /// retract the vote cast on a payout
+    RetractVote,
+    /// mark a payout as spam, its bond is not refunded
+    MarkSpam,
+    /// reject a fraudulent payout immediately, a reason is required
+    Veto,
+    /// publish a bounty on the board, only used in the policy with the
+    /// `bounty` kind
+    PublishBounty,
 }
 
 impl Action {
@@ -60,3 +147,32 @@ pub enum RegistrationResult {
     SuccessWithReferral(u64),
     SuccessWithoutReferral(u64, String),
 }
+
+#[cfg(test)]
+mod tests {
+    use near_sdk::serde_json;
+
+    use super::*;
+
+    #[test]
+    fn usd_converts_to_balance_rounding_down() {
+        // $5 at $2.50 per NEAR
+        assert_eq!(
+            usd_to_balance(USD::from_dollars(5), USD(2_500_000)),
+            2 * ONE_NEAR
+        );
+        // $1 at $3 per NEAR
+        assert_eq!(
+            usd_to_balance(USD::from_dollars(1), USD::from_dollars(3)),
+            333_333_333_333_333_333_333_333
+        );
+        assert_eq!(USD::from_f64(2.7182815), USD(2_718_282));
+    }
+
+    #[test]
+    fn usd_serializes_as_string() {
+        let amount: USD = serde_json::from_str("\"2500000\"").unwrap();
+        assert_eq!(amount, USD(2_500_000));
+        assert_eq!(serde_json::to_string(&amount).unwrap(), "\"2500000\"");
+    }
+}
@@ -6,7 +6,7 @@ use near_sdk::json_types::{Base58CryptoHash, U128};
 use near_sdk::serde::{Deserialize, Serialize};
 use near_sdk::CryptoHash;
 
-use payout::{Bounty, Miscellaneous, Proposal};
+use payout::{Bounty, GovernanceProposal, Miscellaneous, Proposal};
 
 use crate::*;
 
@@ -159,4 +159,36 @@ impl Contract {
             })
             .collect()
     }
+
+    /// Get specific governance proposal
+    pub fn get_governance_proposal(&self, id: u64) -> PayoutOutput<GovernanceProposal> {
+        let proposal = self
+            .governance_proposals
+            .get(&id)
+            .expect(error::ERR_GOVERNANCE_PROPOSAL_NOT_FOUND);
+        PayoutOutput {
+            id,
+            payout: proposal,
+        }
+    }
+
+    /// Get the number of governance proposals, also happens to be the ID of the latest one
+    pub fn get_last_governance_proposal_id(&self) -> u64 {
+        self.last_governance_proposal_id
+    }
+
+    /// Get governance proposals in paginated view.
+    pub fn get_all_governance_proposals(
+        &self,
+        from_index: u64,
+        limit: u64,
+    ) -> Vec<PayoutOutput<GovernanceProposal>> {
+        (from_index..=std::cmp::min(from_index + limit, self.last_governance_proposal_id))
+            .filter_map(|id| {
+                self.governance_proposals
+                    .get(&id)
+                    .map(|p| PayoutOutput { id, payout: p })
+            })
+            .collect()
+    }
 }
@@ -1,14 +1,40 @@
 use near_sdk::borsh::{self, BorshDeserialize, BorshSerialize};
 use near_sdk::serde::{Deserialize, Serialize};
+use near_sdk::AccountId;
 
 use crate::error::ERR_INVALID_ACTION;
 use crate::types::Action;
 
-#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug)]
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
 #[serde(crate = "near_sdk::serde")]
 pub enum Vote {
     Approve,
     Reject,
+    /// counts towards the quorum but neither for nor against the payout
+    Abstain,
+}
+
+/// A vote cast on a payout
+#[derive(BorshSerialize, BorshDeserialize, Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(crate = "near_sdk::serde")]
+pub struct VoteRecord {
+    pub vote: Vote,
+    /// the council member who cast the vote on behalf of the voter through
+    /// a delegation, if the voter did not cast it themselves
+    pub delegate: Option<AccountId>,
+    /// the reason given with the vote, required to reject a payout
+    pub comment: Option<String>,
+}
+
+impl Vote {
+    /// the action that casts this vote
+    pub fn to_action(&self) -> Action {
+        match self {
+            Vote::Approve => Action::VoteApprove,
+            Vote::Reject => Action::VoteReject,
+            Vote::Abstain => Action::VoteAbstain,
+        }
+    }
 }
 
 impl From<Action> for Vote {
@@ -16,6 +42,7 @@ impl From<Action> for Vote {
         match input {
             Action::VoteApprove => Vote::Approve,
             Action::VoteReject => Vote::Reject,
+            Action::VoteAbstain => Vote::Abstain,
             _ => {
                 panic!("{}", ERR_INVALID_ACTION);
             }